@@ -0,0 +1,58 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    // Adding or removing a day changes the directory
+    println!("cargo:rerun-if-changed=src");
+
+    #[cfg(feature = "header")]
+    generate_header(&crate_dir);
+
+    register_solutions(&crate_dir.join("src"), &out_dir.join("solutions.rs"));
+}
+
+/// Regenerate the checked in C header for the intcode library
+///
+/// Only done with the `header` feature, so that normal builds don't need
+/// cbindgen or write to the source tree.
+#[cfg(feature = "header")]
+fn generate_header(crate_dir: &Path) {
+    println!("cargo:rerun-if-changed=src/intcode/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    cbindgen::generate(crate_dir)
+        .expect("Unable to generate the intcode header")
+        .write_to_file(crate_dir.join("include/intcode.h"));
+}
+
+/// Write a `solution!` call listing every `src/dayNN.rs`, for `main` to include
+///
+/// Modules declared in an included file are looked for next to it, so the
+/// days are declared inside a module pointed back at `src`.
+///
+/// cargo fmt can't follow modules declared here, so a test in `scaffold`
+/// checks the formatting of the days instead.
+fn register_solutions(src: &Path, out: &Path) {
+    let mut days = fs::read_dir(src)
+        .expect("Unable to read src")
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter_map(|file| {
+            let day = file.strip_prefix("day")?.strip_suffix(".rs")?;
+            let is_day = day.len() == 2 && day.chars().all(|c| c.is_ascii_digit());
+            is_day.then(|| format!("day{}", day))
+        })
+        .collect::<Vec<_>>();
+    days.sort();
+
+    let module = format!(
+        "#[path = {:?}]\nmod solutions {{\n    solution!({});\n}}\n",
+        src,
+        days.join(", ")
+    );
+
+    fs::write(out, module).expect("Unable to write the solution list");
+}
@@ -0,0 +1,100 @@
+#ifndef INTCODE_H
+#define INTCODE_H
+
+/* Generated by build.rs with cbindgen, do not edit. Run cargo build --features header after changing ffi.rs */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Why `intcode_run` returned
+typedef enum IntcodeStatus {
+  // An output was queued, call `intcode_pop_output` to take it
+  INTCODE_STATUS_OUTPUT = 0,
+  // The program needs input, call `intcode_push_input` and run again
+  INTCODE_STATUS_NEEDS_INPUT = 1,
+  // The program has halted
+  INTCODE_STATUS_HALTED = 2,
+  // The program failed, see `intcode_last_error`
+  INTCODE_STATUS_ERROR = 3,
+} IntcodeStatus;
+
+// A program with its queued inputs and outputs
+typedef struct IntcodeMachine IntcodeMachine;
+
+// Create a machine from `len` values at `code`
+//
+// `code` is copied and may be freed once this returns. The machine must be
+// released with `intcode_free`.
+//
+// # Safety
+//
+// `code` must point to `len` readable values, or be null if `len` is 0.
+struct IntcodeMachine *intcode_create(const int64_t *code, uintptr_t len);
+
+// Grow memory to `size` values, filling new cells with zeros
+//
+// Returns false, leaving memory alone, if `size` is smaller than memory already is.
+//
+// # Safety
+//
+// `machine` must be null or a live machine from `intcode_create`.
+bool intcode_expand(struct IntcodeMachine *machine, int64_t size);
+
+// Queue `value` to be read by the program's next input instruction
+//
+// # Safety
+//
+// `machine` must be null or a live machine from `intcode_create`.
+bool intcode_push_input(struct IntcodeMachine *machine, int64_t value);
+
+// Run until the program outputs a value, needs input or halts
+//
+// # Safety
+//
+// `machine` must be null or a live machine from `intcode_create`.
+enum IntcodeStatus intcode_run(struct IntcodeMachine *machine);
+
+// Take the oldest queued output, returning false if there are none
+//
+// # Safety
+//
+// `machine` must be null or a live machine from `intcode_create`, and
+// `value` must be writable.
+bool intcode_pop_output(struct IntcodeMachine *machine, int64_t *value);
+
+// Read memory at `address`, returning false if it is outside memory
+//
+// # Safety
+//
+// `machine` must be null or a live machine from `intcode_create`, and
+// `value` must be writable.
+bool intcode_read(const struct IntcodeMachine *machine, int64_t address, int64_t *value);
+
+// Write memory at `address`, returning false if it is outside memory
+//
+// # Safety
+//
+// `machine` must be null or a live machine from `intcode_create`.
+bool intcode_write(struct IntcodeMachine *machine, int64_t address, int64_t value);
+
+// The message for the last `IntcodeStatus::Error`, or null if there wasn't one
+//
+// The string belongs to the machine and is valid until the next call that
+// fails or `intcode_free`.
+//
+// # Safety
+//
+// `machine` must be null or a live machine from `intcode_create`.
+const char *intcode_last_error(const struct IntcodeMachine *machine);
+
+// Release a machine from `intcode_create`
+//
+// # Safety
+//
+// `machine` must be null or a live machine from `intcode_create`, and must
+// not be used again.
+void intcode_free(struct IntcodeMachine *machine);
+
+#endif /* INTCODE_H */
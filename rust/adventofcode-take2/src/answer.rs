@@ -0,0 +1,79 @@
+use std::fmt;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// The answer to one part of a puzzle
+///
+/// In JSON each variant is its plain value, a number, string, list of
+/// numbers or list of rows, so answers files stay easy to edit by hand.
+pub enum Answer {
+    Number(i64),
+    Text(String),
+    /// Usually the outputs of an intcode program
+    List(Vec<i64>),
+    /// A picture to be read by eye, one string per row
+    Grid(Vec<String>),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(text) => write!(f, "{}", text),
+            Answer::List(values) => write!(f, "{}", values.iter().join(",")),
+            Answer::Grid(rows) => write!(f, "{}", rows.join("\n")),
+        }
+    }
+}
+
+macro_rules! number_answer {
+    ($($ty:ty),+) => {
+        $(
+            impl From<$ty> for Answer {
+                fn from(n: $ty) -> Self {
+                    Answer::Number(n as i64)
+                }
+            }
+        )+
+    }
+}
+
+number_answer!(i32, i64, u32, usize);
+
+impl From<Vec<i64>> for Answer {
+    fn from(values: Vec<i64>) -> Self {
+        Answer::List(values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Answer::from(42).to_string(), "42");
+        assert_eq!(Answer::from(vec![1, 2, 3]).to_string(), "1,2,3");
+        assert_eq!(
+            Answer::Grid(vec!["#.".to_string(), ".#".to_string()]).to_string(),
+            "#.\n.#"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let answers = vec![
+            Answer::Number(7),
+            Answer::Text("ABC".to_string()),
+            Answer::List(vec![1, 2]),
+            Answer::Grid(vec!["#.".to_string()]),
+        ];
+        let json = serde_json::to_string(&answers).unwrap();
+
+        assert_eq!(json, r##"[7,"ABC",[1,2],["#."]]"##);
+        assert_eq!(serde_json::from_str::<Vec<Answer>>(&json).unwrap(), answers);
+    }
+}
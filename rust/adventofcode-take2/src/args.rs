@@ -1,7 +1,8 @@
 use clap::Clap;
 use tracing_subscriber::EnvFilter;
 
-use crate::Solutions;
+use crate::output::Format;
+use crate::{Command, Output, Solutions};
 
 macro_rules! solution {
     ($($day:ident),+) => {
@@ -9,34 +10,97 @@ macro_rules! solution {
             mod $day;
         )+
 
-        #[derive(Debug, Clap)]
+        #[derive(Debug, clap::Clap)]
         pub enum Solutions {
             $(
             #[allow(non_camel_case_types)]
+            #[clap(about = concat!("Solve ", stringify!($day)))]
             $day {
                 #[clap(subcommand)]
-                contents: crate::$day::Args,
+                part: crate::solution::Part,
             },
             )+
         }
 
-        impl Command for Solutions {
-            fn execute(&self) -> anyhow::Result<String> {
+        impl Solutions {
+            /// Every day, with its parser and examples
+            pub fn days() -> Vec<crate::solution::Entry> {
+                vec![
+                    $(crate::solution::Entry::new::<$day::Day>(
+                        crate::runner::day_number(stringify!($day)),
+                    ),)+
+                ]
+            }
+
+            /// The day and part to solve
+            pub fn selected(&self) -> (crate::solution::Entry, crate::solution::Part) {
                 match self {
-                    $(Self::$day { contents } => contents.execute(),)+
+                    $(Self::$day { part } => (
+                        crate::solution::Entry::new::<$day::Day>(
+                            crate::runner::day_number(stringify!($day)),
+                        ),
+                        *part,
+                    ),)+
                 }
             }
         }
     }
 }
 
+#[derive(Debug, Clap)]
+pub enum Commands {
+    #[clap(flatten)]
+    Solution(Solutions),
+
+    /// Run every solution and print a table of the results
+    All(crate::runner::AllArgs),
+
+    /// Create the module and input for a new day
+    NewDay(crate::scaffold::NewDayArgs),
+
+    /// Time repeated runs of one solution
+    Bench(crate::bench::BenchArgs),
+
+    /// Check solutions against the expected answers
+    Verify(crate::verify::VerifyArgs),
+
+    /// Run arbitrary intcode programs
+    Intcode {
+        #[clap(subcommand)]
+        contents: crate::intcode_cli::Args,
+    },
+}
+
+impl Command for Commands {
+    fn execute(&self) -> anyhow::Result<Output> {
+        match self {
+            Self::Solution(solution) => {
+                let (entry, part) = solution.selected();
+                Ok(Output::Results(crate::runner::run_day(entry, &[part])))
+            }
+            Self::All(args) => crate::runner::all(args),
+            Self::Verify(args) => crate::verify::verify(args).map(Output::Verified),
+            Self::Bench(args) => crate::bench::bench(args).map(Output::Text),
+            Self::NewDay(args) => crate::scaffold::new_day(args).map(Output::Text),
+            Self::Intcode { contents } => contents.execute(),
+        }
+    }
+}
+
 #[derive(Debug, Clap)]
 pub struct Args {
     #[clap(long, default_value = "warn")]
     pub logging_filter: String,
 
+    /// How to print solution results: text, json or csv
+    #[clap(long, default_value = "text")]
+    pub format: Format,
+
+    #[clap(flatten)]
+    pub inputs: crate::inputs::InputArgs,
+
     #[clap(subcommand)]
-    pub command: Solutions,
+    pub command: Commands,
 }
 
 impl Args {
@@ -0,0 +1,254 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use clap::Clap;
+use serde::{Deserialize, Serialize};
+
+use crate::inputs::source_name;
+use crate::solution::Part;
+use crate::Solutions;
+
+#[derive(Debug, Clap)]
+pub struct BenchArgs {
+    day: u32,
+
+    part: u32,
+
+    /// Number of timed runs
+    #[clap(long, default_value = "10")]
+    iterations: usize,
+
+    /// Number of untimed runs before timing starts
+    #[clap(long, default_value = "1")]
+    warmup: usize,
+
+    /// Where to keep the baseline, `target/bench/dayNN-partN.json` by default
+    #[clap(long)]
+    baseline: Option<PathBuf>,
+
+    /// Replace the baseline with this run even if one already exists
+    #[clap(long)]
+    save: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Summary of a set of timings, all in milliseconds
+pub struct Stats {
+    pub min: f64,
+    pub mean: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub std_dev: f64,
+}
+
+impl Stats {
+    /// Summarise `timings`, which must not be empty
+    pub fn new(timings: &[Duration]) -> Stats {
+        let mut ms = timings
+            .iter()
+            .map(|t| t.as_secs_f64() * 1000.0)
+            .collect::<Vec<_>>();
+        ms.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let n = ms.len();
+        let mean = ms.iter().sum::<f64>() / n as f64;
+        let median = match n % 2 {
+            0 => (ms[n / 2 - 1] + ms[n / 2]) / 2.0,
+            _ => ms[n / 2],
+        };
+        // Nearest rank, so small samples report a value that was measured
+        let p95 = ms[((n as f64 * 0.95).ceil() as usize).max(1) - 1];
+        let variance = match n {
+            1 => 0.0,
+            _ => ms.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (n - 1) as f64,
+        };
+
+        Stats {
+            min: ms[0],
+            mean,
+            median,
+            p95,
+            std_dev: variance.sqrt(),
+        }
+    }
+
+    fn rows(&self) -> [(&'static str, f64); 5] {
+        [
+            ("min", self.min),
+            ("mean", self.mean),
+            ("median", self.median),
+            ("p95", self.p95),
+            ("std dev", self.std_dev),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A saved benchmark that later runs are compared with
+struct Baseline {
+    input: String,
+    iterations: usize,
+    stats: Stats,
+}
+
+fn load_baseline(path: &Path) -> Result<Option<Baseline>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read {}", path.display()))?;
+    serde_json::from_str(&text)
+        .map(Some)
+        .with_context(|| format!("Unable to parse {}", path.display()))
+}
+
+fn save_baseline(path: &Path, baseline: &Baseline) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    std::fs::write(path, serde_json::to_string_pretty(baseline)? + "\n")
+        .with_context(|| format!("Unable to write {}", path.display()))
+}
+
+/// Render `stats`, with the change from `baseline` when there is one
+fn render(stats: &Stats, baseline: Option<&Stats>) -> String {
+    let current = stats.rows();
+
+    current
+        .iter()
+        .enumerate()
+        .map(|(i, (name, value))| {
+            let change = match baseline.map(|b| b.rows()[i].1) {
+                Some(before) if before > 0.0 => {
+                    format!("  ({:+.1}%)", (value - before) / before * 100.0)
+                }
+                _ => String::new(),
+            };
+            format!("{:<8}{:>10.3}ms{}", name, value, change)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Time repeated runs of one solution in this process
+///
+/// The input is read and parsed once up front and timed on its own, so the
+/// statistics only cover solving.
+pub fn bench(args: &BenchArgs) -> Result<String> {
+    let part = match args.part {
+        1 => Part::Part1,
+        2 => Part::Part2,
+        _ => return Err(anyhow!("Part {} doesn't exist", args.part)),
+    };
+    let entry = Solutions::days()
+        .into_iter()
+        .find(|entry| entry.day == args.day)
+        .ok_or_else(|| anyhow!("There is no solution for day {}", args.day))?;
+
+    if args.iterations == 0 {
+        return Err(anyhow!("--iterations must be at least 1"));
+    }
+
+    entry.check_example(part)?;
+
+    let name = entry.name();
+    let start = Instant::now();
+    let text = entry.input()?;
+    let load = start.elapsed();
+
+    let start = Instant::now();
+    let solver = (entry.parser)(&text)?;
+    let parse = start.elapsed();
+
+    for _ in 0..args.warmup {
+        solver.solve(part)?;
+    }
+
+    let timings = (0..args.iterations)
+        .map(|_| {
+            let start = Instant::now();
+            solver.solve(part).map(|_| start.elapsed())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let stats = Stats::new(&timings);
+    let part = part.number();
+
+    let path = args.baseline.clone().unwrap_or_else(|| {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("target/bench")
+            .join(format!("{}-part{}.json", name, part))
+    });
+    let input = source_name(&name);
+    let baseline = load_baseline(&path)?;
+    // Timings of a different input aren't comparable
+    let baseline = baseline.filter(|b| b.input == input);
+
+    let mut report = format!(
+        "day {} part {} ({}), {} iterations after {} warmup\n{:<8}{:>10.3}ms\n{:<8}{:>10.3}ms\n{}",
+        entry.day,
+        part,
+        input,
+        args.iterations,
+        args.warmup,
+        "load",
+        load.as_secs_f64() * 1000.0,
+        "parse",
+        parse.as_secs_f64() * 1000.0,
+        render(&stats, baseline.as_ref().map(|b| &b.stats))
+    );
+
+    if args.save || baseline.is_none() {
+        save_baseline(
+            &path,
+            &Baseline {
+                input,
+                iterations: args.iterations,
+                stats,
+            },
+        )?;
+        report.push_str(&format!("\nSaved baseline to {}", path.display()));
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ms(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&v| Duration::from_millis(v)).collect()
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::new(&ms(&[4, 2, 8, 6]));
+
+        assert_eq!(stats.min, 2.0);
+        assert_eq!(stats.mean, 5.0);
+        assert_eq!(stats.median, 5.0);
+        assert_eq!(stats.p95, 8.0);
+        assert!((stats.std_dev - 2.582).abs() < 0.001);
+
+        let single = Stats::new(&ms(&[3]));
+        assert_eq!((single.median, single.p95, single.std_dev), (3.0, 3.0, 0.0));
+    }
+
+    #[test]
+    fn test_render_against_baseline() {
+        let before = Stats::new(&ms(&[10]));
+        let after = Stats::new(&ms(&[12]));
+
+        assert_eq!(
+            render(&after, Some(&before)).lines().next(),
+            Some("min         12.000ms  (+20.0%)")
+        );
+        assert_eq!(
+            render(&after, None).lines().nth(4),
+            Some("std dev      0.000ms")
+        );
+    }
+}
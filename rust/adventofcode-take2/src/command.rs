@@ -1,5 +1,19 @@
 use anyhow::Result;
 
+use crate::runner::SolutionResult;
+use crate::verify::Report;
+
 pub trait Command {
-    fn execute(&self) -> Result<String>;
+    fn execute(&self) -> Result<Output>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// What a command produced
+pub enum Output {
+    /// The results of running solutions, which can be printed in any `Format`
+    Results(Vec<SolutionResult>),
+    /// Results checked against their expected answers, also printed in any `Format`
+    Verified(Report),
+    /// Anything else, printed as it is
+    Text(String),
 }
@@ -1,46 +1,7 @@
 use anyhow::Result;
-use clap::Clap;
 
-use crate::{input, Command};
-
-#[derive(Debug, Clap)]
-pub enum Args {
-    Part1,
-    Part2,
-}
-
-impl Command for Args {
-    fn execute(&self) -> Result<String> {
-        match self {
-            Self::Part1 => part_one(),
-            Self::Part2 => part_two(),
-        }
-    }
-}
-
-fn part_one() -> Result<String> {
-    let module_masses: Vec<_> = input("day01")?
-        .lines()
-        .map(|l| l.parse::<i32>().unwrap())
-        .collect();
-
-    Ok(format!(
-        "{}",
-        total_fuel_required_without_fuel_for_fuel(module_masses)
-    ))
-}
-
-fn part_two() -> Result<String> {
-    let module_masses: Vec<_> = input("day01")?
-        .lines()
-        .map(|l| l.parse::<i32>().unwrap())
-        .collect();
-
-    Ok(format!(
-        "{}",
-        total_fuel_required_with_fuel_for_fuel(module_masses)
-    ))
-}
+use crate::solution::Example;
+use crate::{Answer, Solution};
 
 fn mass_fuel_required(mass: i32) -> i32 {
     mass / 3 - 2
@@ -74,6 +35,44 @@ fn total_fuel_required_with_fuel_for_fuel(module_masses: impl IntoIterator<Item
     module_masses.into_iter().map(module_fuel_required).sum()
 }
 
+pub struct Day;
+
+impl Solution for Day {
+    type Parsed = Vec<i32>;
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        Ok(input
+            .lines()
+            .map(|l| l.parse::<i32>())
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn part1(module_masses: &Self::Parsed) -> Result<Answer> {
+        Ok(total_fuel_required_without_fuel_for_fuel(module_masses.iter().copied()).into())
+    }
+
+    fn part2(module_masses: &Self::Parsed) -> Result<Answer> {
+        Ok(total_fuel_required_with_fuel_for_fuel(module_masses.iter().copied()).into())
+    }
+
+    fn examples() -> Vec<Example> {
+        vec![
+            Example {
+                name: "1969",
+                input: "1969",
+                part1: Some(654.into()),
+                part2: Some(966.into()),
+            },
+            Example {
+                name: "masses",
+                input: "12\n14\n1969\n100756",
+                part1: Some((2 + 2 + 654 + 33583).into()),
+                part2: Some((2 + 2 + 966 + 50346).into()),
+            },
+        ]
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
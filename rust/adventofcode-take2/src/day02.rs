@@ -1,94 +1,53 @@
-use anyhow::Result;
-use clap::Clap;
+use anyhow::{anyhow, Result};
 
-use crate::{input, Command};
+use crate::intcode_cli::parse_values;
+use crate::{Answer, Solution};
+use intcode::io::BasicProgramIO;
+use intcode::isa::InstructionSet;
+use intcode::Program;
 
-#[derive(Debug, Clap)]
-pub enum Args {
-    Part1,
-    Part2,
-}
+fn run_program(program: &mut Program) -> Result<()> {
+    program.set_instruction_set(InstructionSet::Basic);
+    program.run(&mut BasicProgramIO::default())?;
 
-impl Command for Args {
-    fn execute(&self) -> Result<String> {
-        match self {
-            Self::Part1 => part_one(),
-            Self::Part2 => part_two(),
-        }
-    }
+    Ok(())
 }
 
-fn part_one() -> Result<String> {
-    let program: Vec<_> = input("day02")?
-        .split(',')
-        .map(|s| s.trim().parse::<i32>().unwrap())
-        .collect();
+fn run_gravity_assist(program: &Program, noun: i64, verb: i64) -> Result<i64> {
+    let mut program = program.clone();
 
-    Ok(run_gravity_assist(&program, 12, 2).to_string())
-}
+    program.write(1, noun);
+    program.write(2, verb);
 
-fn part_two() -> Result<String> {
-    let program: Vec<_> = input("day02")?
-        .split(',')
-        .map(|s| s.trim().parse::<i32>().unwrap())
-        .collect();
-
-    for noun in 0..100 {
-        for verb in 0..100 {
-            let output = run_gravity_assist(&program, noun, verb);
-            if output == 19_690_720 {
-                return Ok((100 * noun + verb).to_string());
-            }
-        }
-    }
-    panic!("No answer found");
+    run_program(&mut program)?;
+
+    Ok(program.read(0))
 }
 
-fn run_program(program: &mut [i32]) -> &mut [i32] {
-    let mut current_index = 0;
+pub struct Day;
 
-    // println!("pre-run: {:?}", program);
-    while current_index < program.len() {
-        let opcode = program[current_index];
+impl Solution for Day {
+    type Parsed = Program;
 
-        match opcode {
-            1 => {
-                let op1 = program[program[current_index + 1] as usize];
-                let op2 = program[program[current_index + 2] as usize];
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        Ok(Program::new("Day2", &parse_values(input)?))
+    }
 
-                program[program[current_index + 3] as usize] = op1 + op2;
-            }
-            2 => {
-                let op1 = program[program[current_index + 1] as usize];
-                let op2 = program[program[current_index + 2] as usize];
+    fn part1(program: &Self::Parsed) -> Result<Answer> {
+        Ok(run_gravity_assist(program, 12, 2)?.into())
+    }
 
-                program[program[current_index + 3] as usize] = op1 * op2;
-            }
-            99 => {
-                // println!("Halting");
-                break;
-            }
-            n => {
-                panic!("Unknown opcode: {}", n);
+    fn part2(program: &Self::Parsed) -> Result<Answer> {
+        for noun in 0..100 {
+            for verb in 0..100 {
+                let output = run_gravity_assist(program, noun, verb)?;
+                if output == 19_690_720 {
+                    return Ok((100 * noun + verb).into());
+                }
             }
         }
-
-        current_index += 4;
+        Err(anyhow!("No answer found"))
     }
-
-    // println!("post-run: {:?}", program);
-    program
-}
-
-fn run_gravity_assist(program: &[i32], noun: i32, verb: i32) -> i32 {
-    let mut memory: Vec<i32> = program.iter().copied().collect();
-
-    memory[1] = noun;
-    memory[2] = verb;
-
-    run_program(&mut memory);
-
-    memory[0]
 }
 
 #[cfg(test)]
@@ -97,16 +56,29 @@ mod test {
 
     #[test]
     fn test_run_program() {
-        let test_fn = |actual: &mut [i32], expected: &[i32]| {
-            assert_eq!(run_program(actual), expected);
+        let test_fn = |actual: &[i64], expected: &[i64]| {
+            let mut program = Program::new("", actual);
+            run_program(&mut program).unwrap();
+
+            let memory: Vec<_> = (0..expected.len() as i64)
+                .map(|address| program.read(address))
+                .collect();
+            assert_eq!(memory, expected);
         };
 
-        test_fn(&mut [1, 0, 0, 0, 99], &[2, 0, 0, 0, 99]);
-        test_fn(&mut [2, 3, 0, 3, 99], &[2, 3, 0, 6, 99]);
-        test_fn(&mut [2, 4, 4, 5, 99, 0], &[2, 4, 4, 5, 99, 9801]);
+        test_fn(&[1, 0, 0, 0, 99], &[2, 0, 0, 0, 99]);
+        test_fn(&[2, 3, 0, 3, 99], &[2, 3, 0, 6, 99]);
+        test_fn(&[2, 4, 4, 5, 99, 0], &[2, 4, 4, 5, 99, 9801]);
         test_fn(
-            &mut [1, 1, 1, 4, 99, 5, 6, 0, 99],
+            &[1, 1, 1, 4, 99, 5, 6, 0, 99],
             &[30, 1, 1, 4, 2, 5, 6, 0, 99],
         );
     }
+
+    #[test]
+    fn test_rejects_newer_instructions() {
+        let mut program = Program::new("", &[1101, 1, 1, 0, 99]);
+
+        assert!(run_program(&mut program).is_err());
+    }
 }
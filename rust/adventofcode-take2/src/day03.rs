@@ -1,5 +1,4 @@
 use anyhow::Result;
-use clap::Clap;
 use std::collections::HashMap;
 
 type WireGrid = HashMap<(i32, i32), i32>;
@@ -10,40 +9,8 @@ mod wire;
 
 use wire::Wire;
 
-use crate::{input, Command};
-
-#[derive(Debug, Clap)]
-pub enum Args {
-    Part1,
-    Part2,
-}
-
-impl Command for Args {
-    fn execute(&self) -> Result<String> {
-        match self {
-            Self::Part1 => part_one(),
-            Self::Part2 => part_two(),
-        }
-    }
-}
-
-fn part_one() -> Result<String> {
-    let wires: Vec<_> = input("day03")?.lines().map(|l| Wire::new(l)).collect();
-
-    let (grid, intersection_value) = build_grid(&wires);
-    let closest = find_closest_intersection(grid, intersection_value);
-
-    Ok(closest.unwrap().to_string())
-}
-
-fn part_two() -> Result<String> {
-    let wires: Vec<_> = input("day03")?.lines().map(|l| Wire::new(l)).collect();
-
-    let grid = build_grid_with_steps(&wires);
-    let closest = find_closest_intersection_by_steps(grid);
-
-    Ok(closest.unwrap().to_string())
-}
+use crate::solution::Example;
+use crate::{Answer, Solution};
 
 fn build_grid(wires: &[Wire]) -> (WireGrid, i32) {
     let mut grid = WireGrid::new();
@@ -67,7 +34,7 @@ fn build_grid_with_steps(wires: &[Wire]) -> WireGridWithSteps {
         for ((x, y), steps) in wire.to_coords_with_steps() {
             *grid
                 .entry((x, y))
-                .or_insert_with(HashMap::new)
+                .or_default()
                 .entry(idx as i32)
                 .or_default() = steps;
         }
@@ -116,6 +83,47 @@ fn find_closest_intersection(grid: WireGrid, intersection_value: i32) -> Option<
     closest
 }
 
+pub struct Day;
+
+impl Solution for Day {
+    type Parsed = Vec<Wire>;
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        Ok(input.lines().map(Wire::new).collect())
+    }
+
+    fn part1(wires: &Self::Parsed) -> Result<Answer> {
+        let (grid, intersection_value) = build_grid(wires);
+        let closest = find_closest_intersection(grid, intersection_value);
+
+        Ok(closest.unwrap().into())
+    }
+
+    fn part2(wires: &Self::Parsed) -> Result<Answer> {
+        let grid = build_grid_with_steps(wires);
+        let closest = find_closest_intersection_by_steps(grid);
+
+        Ok(closest.unwrap().into())
+    }
+
+    fn examples() -> Vec<Example> {
+        vec![
+            Example {
+                name: "wires-1",
+                input: "R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83",
+                part1: Some(159.into()),
+                part2: Some(610.into()),
+            },
+            Example {
+                name: "wires-2",
+                input: "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7",
+                part1: Some(135.into()),
+                part2: Some(410.into()),
+            },
+        ]
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -20,7 +20,7 @@ impl Wire {
         let mut current_y = 0;
         let mut retvalue = Vec::new();
 
-        for (x, y) in self.movements.iter().map(|m| m.to_coords()).flatten() {
+        for (x, y) in self.movements.iter().flat_map(|m| m.to_coords()) {
             current_x += x;
             current_y += y;
 
@@ -36,12 +36,7 @@ impl Wire {
         let mut current_steps = 0;
         let mut retvalue = Vec::new();
 
-        for ((x, y), steps) in self
-            .movements
-            .iter()
-            .map(|m| m.to_coords_with_steps())
-            .flatten()
-        {
+        for ((x, y), steps) in self.movements.iter().flat_map(|m| m.to_coords_with_steps()) {
             current_x += x;
             current_y += y;
             current_steps += steps;
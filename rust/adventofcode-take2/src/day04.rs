@@ -1,24 +1,6 @@
-use anyhow::Result;
-use clap::Clap;
+use anyhow::{anyhow, Result};
 
-use crate::Command;
-
-const INPUT: &str = "206938-679128";
-
-#[derive(Debug, Clap)]
-pub enum Args {
-    Part1,
-    Part2,
-}
-
-impl Command for Args {
-    fn execute(&self) -> Result<String> {
-        match self {
-            Self::Part1 => part_one(),
-            Self::Part2 => part_two(),
-        }
-    }
-}
+use crate::{Answer, Solution};
 
 fn password_to_digits(n: i32) -> Vec<i32> {
     let mut output = Vec::with_capacity(6);
@@ -35,15 +17,6 @@ fn password_to_digits(n: i32) -> Vec<i32> {
     output
 }
 
-fn parse_input() -> (i32, i32) {
-    let values: Vec<_> = INPUT
-        .split('-')
-        .map(|s| s.parse::<i32>().unwrap())
-        .collect();
-
-    (values[0], values[1])
-}
-
 #[derive(Debug, Clone, Copy)]
 struct Run {
     digit: i32,
@@ -114,16 +87,31 @@ fn valid_passwords_in_range_problem2(min: i32, max: i32) -> i32 {
         .count() as i32
 }
 
-fn part_one() -> Result<String> {
-    let (min, max) = parse_input();
+pub struct Day;
 
-    Ok(valid_passwords_in_range(min, max).to_string())
-}
+impl Solution for Day {
+    type Parsed = (i32, i32);
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        let values = input
+            .trim()
+            .split('-')
+            .map(|s| s.parse::<i32>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match values.as_slice() {
+            [min, max] => Ok((*min, *max)),
+            _ => Err(anyhow!("Expected a range like 100000-200000")),
+        }
+    }
 
-fn part_two() -> Result<String> {
-    let (min, max) = parse_input();
+    fn part1((min, max): &Self::Parsed) -> Result<Answer> {
+        Ok(valid_passwords_in_range(*min, *max).into())
+    }
 
-    Ok(valid_passwords_in_range_problem2(min, max).to_string())
+    fn part2((min, max): &Self::Parsed) -> Result<Answer> {
+        Ok(valid_passwords_in_range_problem2(*min, *max).into())
+    }
 }
 
 #[cfg(test)]
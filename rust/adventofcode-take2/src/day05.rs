@@ -1,47 +1,42 @@
-use crate::intcode::io::BasicProgramIO;
-use crate::intcode::Program;
+use intcode::Program;
 
 use anyhow::Result;
-use clap::Clap;
 
-use crate::{input, Command};
+use crate::intcode_cli::parse_values;
+use crate::solution::Example;
+use crate::{Answer, Solution};
 
-#[derive(Debug, Clap)]
-pub enum Args {
-    Part1,
-    Part2,
-}
+pub struct Day;
 
-impl Command for Args {
-    fn execute(&self) -> Result<String> {
-        match self {
-            Self::Part1 => part_one(),
-            Self::Part2 => part_two(),
-        }
-    }
-}
-
-fn load_program(input: &str) -> Vec<i64> {
-    input
-        .split(',')
-        .map(|s| s.trim().parse::<i64>().unwrap())
-        .collect()
-}
+impl Solution for Day {
+    type Parsed = Vec<i64>;
 
-fn part_one() -> Result<String> {
-    let mut io = BasicProgramIO::new(&[1]);
-    let mut program = Program::new("Day5 - Problem 1", &load_program(&input("day05")?));
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        parse_values(input)
+    }
 
-    program.run(&mut io);
+    fn part1(code: &Self::Parsed) -> Result<Answer> {
+        let mut program = Program::new("Day5 - Problem 1", code);
+        let outputs = program.outputs([1]).collect::<Result<Vec<_>, _>>()?;
 
-    Ok(format!("{:?}", io.outputs()))
-}
+        Ok(outputs.into())
+    }
 
-fn part_two() -> Result<String> {
-    let mut io = BasicProgramIO::new(&[5]);
-    let mut program = Program::new("Day5 - Problem 2", &load_program(&input("day05")?));
+    fn part2(code: &Self::Parsed) -> Result<Answer> {
+        let mut program = Program::new("Day5 - Problem 2", code);
+        let outputs = program.outputs([5]).collect::<Result<Vec<_>, _>>()?;
 
-    program.run(&mut io);
+        Ok(outputs.into())
+    }
 
-    Ok(format!("{:?}", io.outputs()))
+    fn examples() -> Vec<Example> {
+        vec![Example {
+            // Prints 999 below 8, 1000 for 8 and 1001 above it
+            name: "around-8",
+            input: "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,\
+                    125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99",
+            part1: Some(vec![999].into()),
+            part2: Some(vec![999].into()),
+        }]
+    }
 }
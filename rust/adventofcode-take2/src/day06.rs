@@ -5,30 +5,15 @@
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
-use anyhow::Result;
-use clap::Clap;
+use anyhow::{anyhow, Result};
 
-use crate::{input, Command};
-
-#[derive(Debug, Clap)]
-pub enum Args {
-    Part1,
-    Part2,
-}
-
-impl Command for Args {
-    fn execute(&self) -> Result<String> {
-        match self {
-            Self::Part1 => part_one(),
-            Self::Part2 => part_two(),
-        }
-    }
-}
+use crate::solution::Example;
+use crate::{Answer, Solution};
 
 const COM: &str = "COM";
 
 #[derive(Debug, Clone)]
-enum Object {
+pub enum Object {
     CenterOfMass,
     Satellite { name: String, ancestor: Rc<Object> },
 }
@@ -37,7 +22,7 @@ impl Object {
     fn name(&self) -> &str {
         match *self {
             Object::CenterOfMass => COM,
-            Object::Satellite { ref name, .. } => &name,
+            Object::Satellite { ref name, .. } => name,
         }
     }
 
@@ -90,36 +75,35 @@ fn add_to_graph(graph: &mut OrbitGraph, entries: &EntryMap, ancestor: Rc<Object>
     }
 }
 
-fn build_orbit_graph(entries: &EntryMap) -> OrbitGraph {
+fn build_orbit_graph(entries: &EntryMap) -> Result<OrbitGraph> {
     let mut graph = OrbitGraph::new();
-    let com = match entries.get("COM") {
-        None => panic!("COM missing"),
-        Some(e) => e,
-    };
+    let com = entries.get("COM").ok_or_else(|| anyhow!("COM missing"))?;
 
     let com_object = /*CoCreateInstance*/ Rc::new(Object::CenterOfMass);
 
     graph.insert("COM".to_string(), Rc::clone(&com_object));
 
     com.iter()
-        .for_each(|c| add_to_graph(&mut graph, &entries, Rc::clone(&com_object), c));
+        .for_each(|c| add_to_graph(&mut graph, entries, Rc::clone(&com_object), c));
 
-    graph
+    Ok(graph)
 }
 
-fn build_entry_map(input: &str) -> EntryMap {
+fn build_entry_map(input: &str) -> Result<EntryMap> {
     let mut entries = EntryMap::new();
 
     for line in input.lines() {
-        let mut split = line.split(')');
+        let (center, satellite) = line
+            .split_once(')')
+            .ok_or_else(|| anyhow!("Expected an orbit like A)B, not {:?}", line))?;
 
         entries
-            .entry(split.next().unwrap().to_uppercase())
-            .or_insert_with(Vec::new)
-            .push(split.next().unwrap().to_uppercase());
+            .entry(center.to_uppercase())
+            .or_default()
+            .push(satellite.to_uppercase());
     }
 
-    entries
+    Ok(entries)
 }
 
 fn count_all_orbits(graph: &OrbitGraph) -> usize {
@@ -143,8 +127,8 @@ fn find_common_ancestor(obj1: Rc<Object>, obj2: Rc<Object>) -> Rc<Object> {
     unreachable!();
 }
 
-fn build_graph(input: &str) -> OrbitGraph {
-    build_orbit_graph(&build_entry_map(input))
+fn build_graph(input: &str) -> Result<OrbitGraph> {
+    build_orbit_graph(&build_entry_map(input)?)
 }
 
 fn find_distance_between(obj1: Rc<Object>, obj2: Rc<Object>, common_ancestor: Rc<Object>) -> usize {
@@ -156,25 +140,55 @@ fn find_distance_between(obj1: Rc<Object>, obj2: Rc<Object>, common_ancestor: Rc
             .count()
 }
 
-fn part_one() -> Result<String> {
-    let graph = build_graph(&input("day06")?);
+pub struct Day;
 
-    Ok(count_all_orbits(&graph).to_string())
-}
+impl Solution for Day {
+    type Parsed = OrbitGraph;
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        build_graph(input)
+    }
+
+    fn part1(graph: &Self::Parsed) -> Result<Answer> {
+        Ok(count_all_orbits(graph).into())
+    }
 
-fn part_two() -> Result<String> {
-    let graph = build_graph(&input("day06")?);
-    let you = Rc::clone(&graph.get("YOU").expect("Couldnt find YOU"));
-    let santa = Rc::clone(&graph.get("SAN").expect("Couldnt find SAN"));
+    fn part2(graph: &Self::Parsed) -> Result<Answer> {
+        let find = |name| {
+            graph
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("Couldnt find {}", name))
+        };
+        let you = find("YOU")?;
+        let santa = find("SAN")?;
 
-    let common_ancestor = find_common_ancestor(Rc::clone(&you), Rc::clone(&santa));
-    let count = find_distance_between(
-        Rc::clone(&you),
-        Rc::clone(&santa),
-        Rc::clone(&common_ancestor),
-    );
+        let common_ancestor = find_common_ancestor(Rc::clone(&you), Rc::clone(&santa));
+        let count = find_distance_between(
+            Rc::clone(&you),
+            Rc::clone(&santa),
+            Rc::clone(&common_ancestor),
+        );
 
-    Ok(count.to_string())
+        Ok(count.into())
+    }
+
+    fn examples() -> Vec<Example> {
+        vec![
+            Example {
+                name: "orbits",
+                input: "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L",
+                part1: Some(42.into()),
+                part2: None,
+            },
+            Example {
+                name: "transfers",
+                input: "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN",
+                part1: Some(54.into()),
+                part2: Some(4.into()),
+            },
+        ]
+    }
 }
 
 #[cfg(test)]
@@ -185,10 +199,10 @@ mod test {
     fn test_common_ancestry() {
         let input = "COM)B\nB)C\nC)D\nD)E\n E)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN";
 
-        let graph = build_graph(input);
+        let graph = build_graph(input).unwrap();
 
-        let you = Rc::clone(&graph.get("YOU").expect("Couldnt find YOU"));
-        let santa = Rc::clone(&graph.get("SAN").expect("Couldnt find SAN"));
+        let you = Rc::clone(graph.get("YOU").expect("Couldnt find YOU"));
+        let santa = Rc::clone(graph.get("SAN").expect("Couldnt find SAN"));
 
         let common_ancestor = find_common_ancestor(Rc::clone(&you), Rc::clone(&santa));
 
@@ -199,10 +213,10 @@ mod test {
     fn test_ancestry_distance() {
         let input = "COM)B\nB)C\nC)D\nD)E\n E)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN";
 
-        let graph = build_graph(input);
+        let graph = build_graph(input).unwrap();
 
-        let you = Rc::clone(&graph.get("YOU").expect("Couldnt find YOU"));
-        let santa = Rc::clone(&graph.get("SAN").expect("Couldnt find SAN"));
+        let you = Rc::clone(graph.get("YOU").expect("Couldnt find YOU"));
+        let santa = Rc::clone(graph.get("SAN").expect("Couldnt find SAN"));
 
         let common_ancestor = find_common_ancestor(Rc::clone(&you), Rc::clone(&santa));
         let count = find_distance_between(
@@ -213,4 +227,11 @@ mod test {
 
         assert_eq!(count, 4);
     }
+
+    #[test]
+    fn test_bad_input() {
+        assert!(build_graph("garbage").is_err());
+        assert!(build_graph("A)B").is_err());
+        assert!(Day::part2(&build_graph("COM)B").unwrap()).is_err());
+    }
 }
@@ -1,75 +1,16 @@
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::thread;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread::{self, JoinHandle};
 
-use crate::intcode::io::{BasicProgramIO, ProgramIO};
-use crate::intcode::Program;
-use anyhow::Result;
-use clap::Clap;
+use anyhow::{anyhow, Result};
+use intcode::error::IntcodeError;
+use intcode::io::{BasicProgramIO, ChannelEvent, ChannelIO};
+use intcode::Program;
 use itertools::Itertools;
 use tracing::info;
 
-use crate::{input, Command};
-
-#[derive(Debug, Clap)]
-pub enum Args {
-    Part1,
-    Part2,
-}
-
-impl Command for Args {
-    fn execute(&self) -> Result<String> {
-        match self {
-            Self::Part1 => part_one(),
-            Self::Part2 => part_two(),
-        }
-    }
-}
-
-struct ChannelIO {
-    index: i64,
-    input: Receiver<i64>,
-    first_input: Option<i64>,
-    outputs: Vec<i64>,
-    output: Sender<i64>,
-}
-
-impl ChannelIO {
-    fn new(index: i64, first_input: i64, input: Receiver<i64>) -> (ChannelIO, Receiver<i64>) {
-        let (output, next_input) = channel();
-
-        (
-            ChannelIO {
-                index,
-                input,
-                first_input: Some(first_input),
-                outputs: Vec::new(),
-                output,
-            },
-            next_input,
-        )
-    }
-}
-
-impl ProgramIO for ChannelIO {
-    fn get_next_input(&mut self) -> i64 {
-        if let Some(first) = self.first_input {
-            info!("{} - Sending first input {}", self.index, first);
-            self.first_input = None;
-            first
-        } else {
-            info!("{} - Waiting on input", self.index);
-            let next_input = self.input.recv().unwrap();
-            info!("{} - Received input {}", self.index, next_input);
-            next_input
-        }
-    }
-
-    fn receive_output(&mut self, output: i64) {
-        self.outputs.push(output);
-        info!("{} - Sending output {}", self.index, output);
-        self.output.send(output).unwrap()
-    }
-}
+use crate::intcode_cli::parse_values;
+use crate::solution::Example;
+use crate::{Answer, Solution};
 
 struct Amplifier {
     code: Vec<i64>,
@@ -78,128 +19,187 @@ struct Amplifier {
 impl Amplifier {
     fn new(code: &[i64]) -> Amplifier {
         Amplifier {
-            code: code.iter().copied().collect(),
+            code: code.to_vec(),
         }
     }
 
-    fn run(&self, phase_settings: &[i64]) -> i64 {
+    fn run(&self, phase_settings: &[i64]) -> Result<i64> {
         let mut io = BasicProgramIO::new(&[phase_settings[0], 0]);
         let mut program = Program::new("0", &self.code);
 
-        program.run(&mut io);
+        program.run(&mut io)?;
 
         io = BasicProgramIO::new(&[phase_settings[1], io.outputs()[0]]);
         program = Program::new("1", &self.code);
 
-        program.run(&mut io);
+        program.run(&mut io)?;
 
         io = BasicProgramIO::new(&[phase_settings[2], io.outputs()[0]]);
         program = Program::new("2", &self.code);
 
-        program.run(&mut io);
+        program.run(&mut io)?;
 
         io = BasicProgramIO::new(&[phase_settings[3], io.outputs()[0]]);
         program = Program::new("3", &self.code);
 
-        program.run(&mut io);
+        program.run(&mut io)?;
 
         io = BasicProgramIO::new(&[phase_settings[4], io.outputs()[0]]);
         program = Program::new("4", &self.code);
 
-        program.run(&mut io);
+        program.run(&mut io)?;
 
-        io.outputs()[0]
+        Ok(io.outputs()[0])
     }
 
-    fn spawn_thread(&self, index: i64, phase_setting: i64, input: Receiver<i64>) -> Receiver<i64> {
+    fn spawn_thread(
+        &self,
+        index: usize,
+        first_inputs: &[i64],
+        input: Receiver<ChannelEvent>,
+    ) -> (
+        Receiver<ChannelEvent>,
+        JoinHandle<Result<Option<i64>, IntcodeError>>,
+    ) {
         let local_code = self.code.clone();
-        let (mut io, output) = ChannelIO::new(index, phase_setting, input);
+        let (mut io, output) = ChannelIO::new(&index.to_string(), first_inputs, input);
 
-        thread::spawn(move || {
-            // &[settings[0], 0]);
+        let handle = thread::spawn(move || {
             let mut program = Program::new(&format!("Program {}", index), &local_code);
 
-            program.run(&mut io);
+            io.run(&mut program)
         });
 
-        output
+        (output, handle)
     }
 
-    fn feedback_loop(&self, phase_settings: &[i64]) -> i64 {
+    fn feedback_loop(&self, phase_settings: &[i64]) -> Result<i64> {
         let (sender, receiver) = channel();
-        let output1 = self.spawn_thread(0, phase_settings[0], receiver);
+        let mut input = receiver;
+        let mut handles = Vec::new();
 
-        sender.send(0).unwrap();
-
-        let output2 = self.spawn_thread(1, phase_settings[1], output1);
-        let output3 = self.spawn_thread(2, phase_settings[2], output2);
-        let output4 = self.spawn_thread(3, phase_settings[3], output3);
-        let output5 = self.spawn_thread(4, phase_settings[4], output4);
-
-        let mut outputs: Vec<i64> = Vec::new();
-
-        loop {
-            let out = match output5.recv() {
-                Ok(n) => n,
-                Err(e) => panic!("Error with output5.recv: {:?} {}", e, e),
+        for (index, phase_setting) in phase_settings.iter().enumerate() {
+            let first_inputs = if index == 0 {
+                vec![*phase_setting, 0]
+            } else {
+                vec![*phase_setting]
             };
 
-            if out == std::i64::MIN {
-                return *outputs.last().unwrap();
-            } else {
-                outputs.push(out);
+            let (output, handle) = self.spawn_thread(index, &first_inputs, input);
+            input = output;
+            handles.push(handle);
+        }
 
-                if sender.send(out).is_err() {
-                    return out;
+        for event in input.iter() {
+            match event {
+                ChannelEvent::Output(n) => {
+                    info!("Output = {}", n);
+                    // The first amplifier halts before the last so this can fail
+                    let _ = sender.send(ChannelEvent::Output(n));
                 }
-
-                info!("Output = {:?}", outputs);
+                ChannelEvent::Halted => break,
             }
         }
+
+        // Unblock the first amplifier if the loop ended early
+        drop(sender);
+
+        let mut last_output = None;
+        for handle in handles {
+            last_output = handle
+                .join()
+                .map_err(|_| anyhow!("Amplifier thread panicked"))??;
+        }
+
+        last_output.ok_or_else(|| anyhow!("Amplifiers produced no output"))
     }
 }
 
-fn find_highest_output(code: &[i64]) -> i64 {
+fn find_highest_output(code: &[i64]) -> Result<i64> {
     let mut current_max = 0;
     let inputs = [0, 1, 2, 3, 4];
 
     for permutation in inputs.iter().copied().permutations(5) {
-        let result = Amplifier::new(code).run(&permutation);
+        let result = Amplifier::new(code).run(&permutation)?;
 
         if result > current_max {
             current_max = result;
         }
     }
-    current_max
+    Ok(current_max)
 }
 
-fn find_highest_output_from_feedback(code: &[i64]) -> i64 {
+fn find_highest_output_from_feedback(code: &[i64]) -> Result<i64> {
     let mut current_max = 0;
     let inputs = [5, 6, 7, 8, 9];
 
     for permutation in inputs.iter().copied().permutations(5) {
-        let result = Amplifier::new(code).feedback_loop(&permutation);
+        let result = Amplifier::new(code).feedback_loop(&permutation)?;
 
         if result > current_max {
             current_max = result;
         }
     }
-    current_max
+    Ok(current_max)
 }
 
-fn load_program(input: &str) -> Vec<i64> {
-    input
-        .split(',')
-        .map(|s| s.trim().parse::<i64>().unwrap())
-        .collect()
-}
+pub struct Day;
 
-fn part_one() -> Result<String> {
-    Ok(find_highest_output(&load_program(&input("day07")?)).to_string())
-}
+impl Solution for Day {
+    type Parsed = Vec<i64>;
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        parse_values(input)
+    }
+
+    fn part1(code: &Self::Parsed) -> Result<Answer> {
+        Ok(find_highest_output(code)?.into())
+    }
+
+    fn part2(code: &Self::Parsed) -> Result<Answer> {
+        Ok(find_highest_output_from_feedback(code)?.into())
+    }
 
-fn part_two() -> Result<String> {
-    Ok(find_highest_output_from_feedback(&load_program(&input("day07")?)).to_string())
+    fn examples() -> Vec<Example> {
+        vec![
+            Example {
+                name: "amplifiers-1",
+                input: "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+                part1: Some(43210.into()),
+                part2: None,
+            },
+            Example {
+                name: "amplifiers-2",
+                input: "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
+                part1: Some(54321.into()),
+                part2: None,
+            },
+            Example {
+                name: "amplifiers-3",
+                input:
+                    "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,\
+                        1,32,31,31,4,31,99,0,0,0",
+                part1: Some(65210.into()),
+                part2: None,
+            },
+            Example {
+                name: "feedback-1",
+                input:
+                    "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,\
+                        6,99,0,0,5",
+                part1: None,
+                part2: Some(139629729.into()),
+            },
+            Example {
+                name: "feedback-2",
+                input: "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,\
+                        1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,\
+                        56,1005,56,6,99,0,0,0,0,10",
+                part1: None,
+                part2: Some(18216.into()),
+            },
+        ]
+    }
 }
 
 #[cfg(test)]
@@ -207,7 +207,7 @@ mod test {
     use super::*;
 
     fn run_amp(code: Vec<i64>, inputs: Vec<i64>) -> i64 {
-        Amplifier::new(&code).run(&inputs)
+        Amplifier::new(&code).run(&inputs).unwrap()
     }
 
     #[test]
@@ -269,11 +269,6 @@ mod test {
     }
     */
 
-    /* TODO: These are unstable because the last thread can drop before the last result is read
-     *       I may revisit this but it's difficult to fix without changing the intcode being run
-     *
-     *       An opcode to return a final result would be preferable to what's going on now.
-     *
     #[test]
     fn test_feedback_examples() {
         assert_eq!(
@@ -281,7 +276,8 @@ mod test {
                 3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28,
                 -1, 28, 1005, 28, 6, 99, 0, 0, 5
             ])
-            .feedback_loop(&[9, 8, 7, 6, 5]),
+            .feedback_loop(&[9, 8, 7, 6, 5])
+            .unwrap(),
             139629729
         );
         assert_eq!(
@@ -290,7 +286,8 @@ mod test {
                 54, -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53,
                 55, 53, 4, 53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10
             ])
-            .feedback_loop(&[9, 7, 8, 5, 6]),
+            .feedback_loop(&[9, 7, 8, 5, 6])
+            .unwrap(),
             18216
         );
     }
@@ -301,7 +298,8 @@ mod test {
             find_highest_output_from_feedback(&[
                 3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28,
                 -1, 28, 1005, 28, 6, 99, 0, 0, 5
-            ]),
+            ])
+            .unwrap(),
             139629729
         );
         assert_eq!(
@@ -309,9 +307,9 @@ mod test {
                 3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001,
                 54, -5, 54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53,
                 55, 53, 4, 53, 1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10
-            ]),
+            ])
+            .unwrap(),
             18216
         );
     }
-    */
 }
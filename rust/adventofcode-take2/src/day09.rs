@@ -1,48 +1,68 @@
 use anyhow::Result;
-use clap::Clap;
 
-use crate::intcode::io::BasicProgramIO;
-use crate::intcode::Program;
-use crate::{input, Command};
+use crate::intcode_cli::parse_values;
+use crate::solution::Example;
+use crate::{Answer, Solution};
+use intcode::Program;
 
-#[derive(Debug, Clap)]
-pub enum Args {
-    Part1,
-    Part2,
-}
+pub struct Day;
+
+impl Solution for Day {
+    type Parsed = Program;
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        let mut program = Program::new("BOOST", &parse_values(input)?);
+        program.expand();
 
-impl Command for Args {
-    fn execute(&self) -> Result<String> {
-        match self {
-            Self::Part1 => part_one(),
-            Self::Part2 => part_two(),
-        }
+        Ok(program)
     }
-}
 
-fn part_one() -> Result<String> {
-    let mut io = BasicProgramIO::new(&[1]);
-    let mut program = Program::from_str("Test Mode", &input("day09")?);
-    program.expand();
+    fn part1(program: &Self::Parsed) -> Result<Answer> {
+        let mut program = program.clone();
+        let outputs = program.outputs([1]).collect::<Result<Vec<_>, _>>()?;
 
-    program.run(&mut io);
+        Ok(outputs.into())
+    }
 
-    Ok(format!("{:?}", io.outputs()))
-}
+    fn part2(program: &Self::Parsed) -> Result<Answer> {
+        let mut program = program.clone();
+        let outputs = program.outputs([2]).collect::<Result<Vec<_>, _>>()?;
 
-fn part_two() -> Result<String> {
-    let mut io = BasicProgramIO::new(&[2]);
-    let mut program = Program::from_str("Boost Mode", &input("day09")?);
-    program.expand();
+        Ok(outputs.into())
+    }
 
-    program.run(&mut io);
+    fn examples() -> Vec<Example> {
+        let quine = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
 
-    Ok(format!("{:?}", io.outputs()))
+        vec![
+            Example {
+                name: "quine",
+                input: "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99",
+                part1: Some(quine.clone().into()),
+                part2: Some(quine.into()),
+            },
+            Example {
+                name: "16-digits",
+                input: "1102,34915192,34915192,7,4,7,99,0",
+                part1: Some(vec![1219070632396864].into()),
+                part2: Some(vec![1219070632396864].into()),
+            },
+            Example {
+                name: "large-output",
+                input: "104,1125899906842624,99",
+                part1: Some(vec![1125899906842624].into()),
+                part2: Some(vec![1125899906842624].into()),
+            },
+        ]
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use intcode::io::BasicProgramIO;
 
     #[test]
     fn test_problem1_examples() {}
@@ -55,7 +75,7 @@ mod test {
         let mut program = Program::new("Test 16 Digits", &code);
         program.expand();
 
-        program.run(&mut io);
+        program.run(&mut io).unwrap();
 
         assert_eq!(io.outputs()[0].to_string().len(), 16);
     }
@@ -67,7 +87,7 @@ mod test {
         let mut program = Program::new("Test 64 bit output", &code);
         program.expand();
 
-        program.run(&mut io);
+        program.run(&mut io).unwrap();
 
         assert_eq!(io.outputs()[0], 1125899906842624);
     }
@@ -82,19 +102,17 @@ mod test {
         let mut program = Program::new("Test Quine", &code);
         program.expand();
 
-        program.run(&mut io);
+        program.run(&mut io).unwrap();
 
         assert_eq!(io.outputs(), code);
     }
 
     #[test]
     fn test_diagnostic() {
-        let mut io = BasicProgramIO::new(&[1]);
-        let mut program = Program::from_str("Test Run Diagnostic", &crate::inputs::load_input(9));
+        let code = parse_values(include_str!("../../../inputs/day09.txt")).unwrap();
+        let mut program = Program::new("Test Run Diagnostic", &code);
         program.expand();
 
-        program.run(&mut io);
-
-        assert_eq!(io.outputs(), [3241900951]);
+        assert_eq!(program.outputs([1]).last(), Some(Ok(3241900951)));
     }
 }
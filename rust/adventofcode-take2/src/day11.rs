@@ -1,30 +1,15 @@
 use anyhow::Result;
-use clap::Clap;
 
-use crate::intcode::io::ProgramIO;
-use crate::intcode::Program;
+use crate::intcode_cli::parse_values;
 use crate::point::Point;
-use crate::{input, Command};
+use crate::{Answer, Solution};
+use intcode::framed::{FrameHandler, FramedIO};
+use intcode::Program;
 use std::convert::TryFrom;
 use std::error::Error;
 use std::fmt;
 use tracing::info;
 
-#[derive(Debug, Clap)]
-pub enum Args {
-    Part1,
-    Part2,
-}
-
-impl Command for Args {
-    fn execute(&self) -> Result<String> {
-        match self {
-            Self::Part1 => part_one(),
-            Self::Part2 => part_two(),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 /// Generic error just to have an error type to use
 struct GenericError {}
@@ -142,25 +127,18 @@ impl Canvas {
         self.pixels[(pos.y * self.width + pos.x) as usize]
     }
 
-    #[allow(dead_code)]
-    fn render(&self) {
-        print!("\n\n");
-        for y in 0..self.height {
-            print!("  ");
-            for x in 0..self.width {
-                let pixel = self.pixels[(y * self.width + x) as usize];
-                print!(
-                    "{}",
-                    match pixel {
-                        Color::Black => " ",
-                        Color::White => "#",
-                        Color::Unknown => ".",
-                    }
-                );
-            }
-            println!();
-        }
-        println!("\n");
+    /// Draw the canvas one row at a time, with `#` for white pixels
+    fn render(&self) -> Vec<String> {
+        (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| match self.pixels[(y * self.width + x) as usize] {
+                        Color::White => '#',
+                        Color::Black | Color::Unknown => '.',
+                    })
+                    .collect()
+            })
+            .collect()
     }
 
     fn dump(&self) -> &[Color] {
@@ -230,30 +208,38 @@ impl Robot {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-/// What input is the program expecting
-enum IOState {
-    WaitingForColor,
-    WaitingForDirection,
+/// A single paint and move command from the robot's program
+struct RobotCommand {
+    color: Color,
+    rotation: Rotation,
+}
+
+/// Convert a color and rotation output pair to a RobotCommand
+impl TryFrom<[i64; 2]> for RobotCommand {
+    type Error = GenericError;
+
+    fn try_from(frame: [i64; 2]) -> Result<Self, Self::Error> {
+        Ok(RobotCommand {
+            color: Color::try_from(frame[0])?,
+            rotation: Rotation::try_from(frame[1])?,
+        })
+    }
 }
 
 #[derive(Debug)]
 /// IO for the robot
 struct RobotProgramIO<'a> {
-    state: IOState,
     canvas: &'a mut Canvas,
     robot: &'a mut Robot,
 }
 
 impl<'a> RobotProgramIO<'a> {
     fn new(canvas: &'a mut Canvas, robot: &'a mut Robot) -> RobotProgramIO<'a> {
-        RobotProgramIO {
-            state: IOState::WaitingForColor,
-            canvas,
-            robot,
-        }
+        RobotProgramIO { canvas, robot }
     }
 }
-impl<'a> ProgramIO for RobotProgramIO<'a> {
+
+impl<'a> FrameHandler<RobotCommand> for RobotProgramIO<'a> {
     fn get_next_input(&mut self) -> i64 {
         let position = self.robot.position();
         info!("Input -> current_pos={:?}", position);
@@ -263,68 +249,63 @@ impl<'a> ProgramIO for RobotProgramIO<'a> {
 
         pixel.into()
     }
-    fn receive_output(&mut self, output: i64) {
-        match self.state {
-            IOState::WaitingForColor => {
-                info!("Output -> color={:?}", Color::try_from(output).unwrap());
-                self.state = IOState::WaitingForDirection;
-                self.canvas
-                    .paint(self.robot.position(), Color::try_from(output).unwrap());
-                self.state = IOState::WaitingForDirection;
-                /*
-                self.canvas.render();
-                std::thread::sleep(std::time::Duration::from_millis(50));
-                */
-            }
-            IOState::WaitingForDirection => {
-                info!(
-                    "Output -> rotation={:?}",
-                    Rotation::try_from(output).unwrap()
-                );
-                self.state = IOState::WaitingForColor;
-                self.robot.turn(Rotation::try_from(output).unwrap());
-                info!("Output -> direction={:?}", self.robot.direction(),);
-                self.robot.advance();
-                info!("Output -> new position={:?}", self.robot.position());
-            }
-        };
+
+    fn receive_message(&mut self, command: RobotCommand) {
+        info!("Output -> command={:?}", command);
+        self.canvas.paint(self.robot.position(), command.color);
+        self.robot.turn(command.rotation);
+        info!("Output -> direction={:?}", self.robot.direction(),);
+        self.robot.advance();
+        info!("Output -> new position={:?}", self.robot.position());
     }
 }
 
-fn paint_the_hull(name: &str, code: &str, canvas: &mut Canvas, starting_point: Point) {
-    let mut program = Program::from_str(name, code);
+fn paint_the_hull(program: &Program, canvas: &mut Canvas, starting_point: Point) -> Result<()> {
+    let mut program = program.clone();
     let mut robot = Robot::new(starting_point);
-    let mut io = RobotProgramIO::new(canvas, &mut robot);
+    let mut io: FramedIO<RobotCommand, _, 2> =
+        FramedIO::new(RobotProgramIO::new(canvas, &mut robot));
+
+    program.run(&mut io)?;
+    io.finish()?;
 
-    program.expand();
-    program.run(&mut io);
+    Ok(())
 }
 
-fn part_one() -> Result<String> {
-    let mut canvas = Canvas::new(80, 70);
-    let start = Point::new(canvas.width(), canvas.height()) / 2;
+pub struct Day;
 
-    paint_the_hull("testing", &input("day11")?, &mut canvas, start);
+impl Solution for Day {
+    type Parsed = Program;
 
-    canvas.render();
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        let mut program = Program::new("Hull painting robot", &parse_values(input)?);
+        program.expand();
 
-    let painted_count: usize = canvas
-        .dump()
-        .iter()
-        .filter(|x| **x != Color::Unknown)
-        .count();
+        Ok(program)
+    }
 
-    Ok(format!("Number of painted pixels = {}", painted_count))
-}
+    fn part1(program: &Self::Parsed) -> Result<Answer> {
+        let mut canvas = Canvas::new(80, 70);
+        let start = Point::new(canvas.width(), canvas.height()) / 2;
+
+        paint_the_hull(program, &mut canvas, start)?;
 
-fn part_two() -> Result<String> {
-    let mut canvas = Canvas::new(43, 6);
-    canvas.paint(Point::default(), Color::White);
-    paint_the_hull("testing", &input("day11")?, &mut canvas, Point::default());
+        let painted_count: usize = canvas
+            .dump()
+            .iter()
+            .filter(|x| **x != Color::Unknown)
+            .count();
 
-    canvas.render();
+        Ok(painted_count.into())
+    }
+
+    fn part2(program: &Self::Parsed) -> Result<Answer> {
+        let mut canvas = Canvas::new(43, 6);
+        canvas.paint(Point::default(), Color::White);
+        paint_the_hull(program, &mut canvas, Point::default())?;
 
-    Ok("".to_string())
+        Ok(Answer::Grid(canvas.render()))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,136 @@
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use clap::Clap;
+
+/// Where inputs live unless `--inputs-dir` says otherwise, so the binary
+/// finds them whatever directory it is run from
+const DEFAULT_INPUTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/inputs");
+
+static INPUTS_DIR: OnceLock<PathBuf> = OnceLock::new();
+static OVERRIDE: OnceLock<InputSource> = OnceLock::new();
+static EXAMPLE: OnceLock<String> = OnceLock::new();
+
+// Options choosing the input solutions read. Not a doc comment, which
+// clap would use as the about text of the whole program.
+#[derive(Debug, Clap)]
+pub struct InputArgs {
+    /// Read the puzzle input from this file, or from stdin for `-`. Relative
+    /// paths are resolved against the inputs directory
+    #[clap(long, conflicts_with = "input-str")]
+    input: Option<String>,
+
+    /// Use this text as the puzzle input
+    #[clap(long)]
+    input_str: Option<String>,
+
+    /// Use the day's example with this name, from the puzzle text, as the input
+    #[clap(long, conflicts_with_all = &["input", "input-str"])]
+    example: Option<String>,
+
+    /// Directory holding the `dayNN.txt` inputs
+    #[clap(long)]
+    inputs_dir: Option<PathBuf>,
+}
+
+impl InputArgs {
+    /// Make the chosen input the one every solution reads
+    ///
+    /// Stdin is read straight away, so that every day run reads the same text.
+    pub fn install(&self) -> Result<()> {
+        if let Some(dir) = &self.inputs_dir {
+            INPUTS_DIR.get_or_init(|| dir.clone());
+        }
+
+        if let Some(name) = &self.example {
+            EXAMPLE.get_or_init(|| name.clone());
+        }
+
+        let source = match (&self.input, &self.input_str) {
+            (Some(path), _) if path == "-" => {
+                let mut text = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut text)
+                    .context("Unable to read the input from stdin")?;
+                InputSource::Stdin(text)
+            }
+            (Some(path), _) => InputSource::File(inputs_dir().join(path)),
+            (None, Some(text)) => InputSource::Inline(text.clone()),
+            (None, None) => return Ok(()),
+        };
+
+        OVERRIDE.get_or_init(|| source);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Where a solution's input comes from
+pub enum InputSource {
+    File(PathBuf),
+    Stdin(String),
+    Inline(String),
+}
+
+impl InputSource {
+    fn read(&self) -> Result<String> {
+        match self {
+            InputSource::File(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("Unable to read {}", path.display())),
+            InputSource::Stdin(text) | InputSource::Inline(text) => Ok(text.clone()),
+        }
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputSource::File(path) => write!(f, "{}", path.display()),
+            InputSource::Stdin(_) => write!(f, "stdin"),
+            InputSource::Inline(_) => write!(f, "inline"),
+        }
+    }
+}
+
+pub fn inputs_dir() -> &'static Path {
+    INPUTS_DIR.get_or_init(|| PathBuf::from(DEFAULT_INPUTS_DIR))
+}
+
+/// Where the input for the day called `name` will be read from
+pub fn source(name: &str) -> InputSource {
+    OVERRIDE
+        .get()
+        .cloned()
+        .unwrap_or_else(|| InputSource::File(inputs_dir().join(format!("{}.txt", name))))
+}
+
+/// The example chosen with `--example`, which days look up for themselves
+pub fn example() -> Option<&'static str> {
+    EXAMPLE.get().map(String::as_str)
+}
+
+/// A short name for the input the day called `name` reads, such as `day07`
+/// for its usual input, the path given with `--input` or `example:larger`
+pub fn source_name(name: &str) -> String {
+    if let Some(example) = example() {
+        return format!("example:{}", example);
+    }
+
+    match OVERRIDE.get() {
+        None => name.to_string(),
+        Some(InputSource::File(path)) => path
+            .strip_prefix(inputs_dir())
+            .unwrap_or(path)
+            .display()
+            .to_string(),
+        Some(source) => source.to_string(),
+    }
+}
+
+/// Read the input for the day called `name`, such as `day07`
+pub fn input(name: &str) -> Result<String> {
+    source(name).read().with_context(|| name.to_string())
+}
@@ -0,0 +1,473 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use tracing::info;
+
+use super::memory::ProgramMemory;
+use super::sanitizer::Sanitizer;
+
+/// Something that happened on the debugger's connection
+enum ClientEvent {
+    Connected,
+    Line(String),
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Paused,
+    Running,
+    /// Pause once this many more instructions have run
+    Stepping(usize),
+}
+
+/// Lets a client on a TCP connection control a running program
+///
+/// A debugger is attached with `Program::attach_debugger`, and is consulted
+/// before every instruction the program runs, whatever is driving it. The
+/// program starts paused until a client connects and continues it, so it
+/// can be attached to a machine deep inside a network of them.
+///
+/// The protocol is one command per line. Each command gets a single line
+/// reply starting with `ok` or `error`, and the debugger sends
+/// `stopped <pc>` or `halted <pc>` lines on its own when the program
+/// stops. Commands are:
+///
+/// - `break <address>`, `delete <address>` and `breakpoints`
+/// - `step [count]`, `continue` and `pause`
+/// - `registers`, which replies with `pc=<pc> rb=<relative base>`
+/// - `read <address> [count]` and `write <address> <value>...`
+/// - `detach`, which clears breakpoints and lets the program run freely
+///
+/// Only one client is served at a time. When it disconnects the program
+/// keeps running as if it had detached.
+pub struct Debugger {
+    address: SocketAddr,
+    events: Receiver<ClientEvent>,
+    client: Arc<Mutex<Option<TcpStream>>>,
+    breakpoints: BTreeSet<i64>,
+    state: State,
+    /// The breakpoint just continued from, which shouldn't stop again
+    resumed_at: Option<i64>,
+}
+
+impl fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Debugger")
+            .field("address", &self.address)
+            .field("breakpoints", &self.breakpoints)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+impl Debugger {
+    /// Listen for a client on `address`, usually `127.0.0.1:<port>`
+    pub fn listen(address: impl ToSocketAddrs) -> io::Result<Debugger> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let client = Arc::new(Mutex::new(None));
+        let (sender, events) = channel();
+
+        let shared = Arc::clone(&client);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let reader = match stream.try_clone() {
+                    Ok(reader) => BufReader::new(reader),
+                    Err(_) => continue,
+                };
+
+                *shared.lock().unwrap() = Some(stream);
+                if sender.send(ClientEvent::Connected).is_err() {
+                    return;
+                }
+
+                for line in reader.lines().map_while(Result::ok) {
+                    if sender.send(ClientEvent::Line(line)).is_err() {
+                        return;
+                    }
+                }
+
+                *shared.lock().unwrap() = None;
+                if sender.send(ClientEvent::Disconnected).is_err() {
+                    return;
+                }
+            }
+        });
+
+        info!("Debugger listening on {}", address);
+
+        Ok(Debugger {
+            address,
+            events,
+            client,
+            breakpoints: BTreeSet::new(),
+            state: State::Paused,
+            resumed_at: None,
+        })
+    }
+
+    /// The address clients should connect to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Called before every instruction, blocking for as long as the program is paused
+    ///
+    /// Writes from the client are recorded with `sanitizer`, if there is one.
+    pub(crate) fn before_step(
+        &mut self,
+        memory: &mut ProgramMemory,
+        mut sanitizer: Option<&mut Sanitizer>,
+    ) {
+        let pc = memory.current_address();
+
+        // Commands such as `pause` can arrive while the program is running
+        while let Ok(event) = self.events.try_recv() {
+            self.handle(event, memory, sanitizer.as_deref_mut());
+        }
+
+        match self.state {
+            State::Running if self.breakpoints.contains(&pc) && self.resumed_at != Some(pc) => {
+                self.stop(memory)
+            }
+            State::Stepping(0) => self.stop(memory),
+            State::Stepping(n) => self.state = State::Stepping(n - 1),
+            State::Running | State::Paused => {}
+        }
+
+        while self.state == State::Paused {
+            match self.events.recv() {
+                Ok(event) => self.handle(event, memory, sanitizer.as_deref_mut()),
+                // Nobody can ever connect again, so don't wait forever
+                Err(_) => self.state = State::Running,
+            }
+        }
+
+        if memory.get(pc).map(|op| op % 100) == Some(99) {
+            self.send(&format!("halted {}", pc));
+        }
+
+        self.resumed_at = None;
+    }
+
+    fn stop(&mut self, memory: &ProgramMemory) {
+        self.state = State::Paused;
+        self.send(&format!("stopped {}", memory.current_address()));
+    }
+
+    fn handle(
+        &mut self,
+        event: ClientEvent,
+        memory: &mut ProgramMemory,
+        sanitizer: Option<&mut Sanitizer>,
+    ) {
+        match event {
+            ClientEvent::Connected => {
+                let state = match self.state {
+                    State::Paused => "stopped",
+                    _ => "running",
+                };
+                self.send(&format!("{} {}", state, memory.current_address()));
+            }
+            ClientEvent::Line(line) => {
+                let reply = match self.command(&line, memory, sanitizer) {
+                    Ok(Some(reply)) => format!("ok {}", reply),
+                    Ok(None) => "ok".to_string(),
+                    Err(error) => format!("error {}", error),
+                };
+                self.send(&reply);
+            }
+            ClientEvent::Disconnected => self.detach(),
+        }
+    }
+
+    fn command(
+        &mut self,
+        line: &str,
+        memory: &mut ProgramMemory,
+        sanitizer: Option<&mut Sanitizer>,
+    ) -> Result<Option<String>, String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let args = words
+            .map(|word| {
+                word.parse::<i64>()
+                    .map_err(|_| format!("invalid number {}", word))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let paused = self.state == State::Paused;
+
+        match (command, args.as_slice()) {
+            ("break", [address]) => {
+                self.breakpoints.insert(*address);
+                Ok(None)
+            }
+            ("delete", [address]) => match self.breakpoints.remove(address) {
+                true => Ok(None),
+                false => Err(format!("no breakpoint at {}", address)),
+            },
+            ("breakpoints", []) => Ok(Some(
+                self.breakpoints
+                    .iter()
+                    .map(|b| b.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            )),
+            // The instruction about to run counts as the first step
+            ("step", []) if paused => {
+                self.resume(State::Stepping(0), memory);
+                Ok(None)
+            }
+            ("step", [count]) if paused && *count > 0 => {
+                self.resume(State::Stepping(*count as usize - 1), memory);
+                Ok(None)
+            }
+            ("continue", []) if paused => {
+                self.resume(State::Running, memory);
+                Ok(None)
+            }
+            ("step", _) | ("continue", []) => Err("not paused".to_string()),
+            ("pause", []) if paused => Ok(None),
+            ("pause", []) => {
+                // Stops before the next instruction
+                self.state = State::Stepping(0);
+                Ok(None)
+            }
+            ("registers", []) => Ok(Some(format!(
+                "pc={} rb={}",
+                memory.current_address(),
+                memory.relative_base()
+            ))),
+            ("read", [address]) => read(memory, *address, 1).map(Some),
+            ("read", [address, count]) => read(memory, *address, *count).map(Some),
+            ("write", [address, values @ ..]) if !values.is_empty() => {
+                let end = range_end(memory, *address, values.len() as i64)?;
+                let mut sanitizer = sanitizer;
+                for (cell, value) in (*address..end).zip(values) {
+                    if let Some(sanitizer) = sanitizer.as_deref_mut() {
+                        sanitizer.record_write(cell);
+                    }
+                    memory[cell] = *value;
+                }
+                Ok(None)
+            }
+            ("detach", []) => {
+                self.detach();
+                Ok(None)
+            }
+            _ => Err(format!("unknown command {}", line.trim())),
+        }
+    }
+
+    fn resume(&mut self, state: State, memory: &ProgramMemory) {
+        self.state = state;
+        self.resumed_at = Some(memory.current_address());
+    }
+
+    fn detach(&mut self) {
+        self.breakpoints.clear();
+        self.state = State::Running;
+    }
+
+    /// Send a line to the client, if there is one
+    fn send(&self, line: &str) {
+        if let Some(client) = self.client.lock().unwrap().as_mut() {
+            // A client that has gone away will be noticed by the reader
+            let _ = writeln!(client, "{}", line);
+        }
+    }
+}
+
+/// The end of `count` cells from `address`, if there are any and they are all in memory
+fn range_end(memory: &ProgramMemory, address: i64, count: i64) -> Result<i64, String> {
+    match address.checked_add(count) {
+        Some(end) if address >= 0 && count >= 1 && end <= memory.len() => Ok(end),
+        Some(end) => Err(format!("{}..{} is outside of memory", address, end)),
+        None => Err(format!(
+            "{} plus {} cells is outside of memory",
+            address, count
+        )),
+    }
+}
+
+fn read(memory: &ProgramMemory, address: i64, count: i64) -> Result<String, String> {
+    let end = range_end(memory, address, count)?;
+
+    Ok((address..end)
+        .map(|a| memory[a].to_string())
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::io::BasicProgramIO;
+    use crate::{Program, Step};
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(address: SocketAddr) -> Client {
+            let writer = TcpStream::connect(address).unwrap();
+            // Fail rather than hang if the program dies while a reply is due
+            writer
+                .set_read_timeout(Some(std::time::Duration::from_secs(10)))
+                .unwrap();
+            let reader = BufReader::new(writer.try_clone().unwrap());
+            Client { reader, writer }
+        }
+
+        fn receive(&mut self) -> String {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            line.trim_end().to_string()
+        }
+
+        fn send(&mut self, command: &str) -> String {
+            writeln!(self.writer, "{}", command).unwrap();
+            self.receive()
+        }
+    }
+
+    #[test]
+    fn test_debugger() {
+        let debugger = Debugger::listen("127.0.0.1:0").unwrap();
+        let address = debugger.local_addr();
+
+        let mut program = Program::new("", &[1101, 1, 1, 9, 109, 5, 4, 9, 99, 0]);
+        program.attach_debugger(debugger);
+
+        let handle = thread::spawn(move || {
+            let mut io = BasicProgramIO::new(&[]);
+            program.run(&mut io).unwrap();
+            io.outputs().to_vec()
+        });
+
+        let mut client = Client::connect(address);
+        assert_eq!(client.receive(), "stopped 0");
+        assert_eq!(client.send("registers"), "ok pc=0 rb=0");
+        assert_eq!(client.send("break 6"), "ok");
+        assert_eq!(client.send("breakpoints"), "ok 6");
+        assert_eq!(client.send("continue"), "ok");
+        assert_eq!(client.receive(), "stopped 6");
+        assert_eq!(client.send("registers"), "ok pc=6 rb=5");
+        assert_eq!(client.send("read 9"), "ok 2");
+        assert_eq!(client.send("write 9 40"), "ok");
+        assert_eq!(
+            client.send("read 100"),
+            "error 100..101 is outside of memory"
+        );
+        assert_eq!(client.send("step"), "ok");
+        assert_eq!(client.receive(), "stopped 8");
+        assert_eq!(client.send("continue"), "ok");
+        assert_eq!(client.receive(), "halted 8");
+
+        assert_eq!(handle.join().unwrap(), vec![40]);
+    }
+
+    #[test]
+    fn test_debugger_disconnect() {
+        let debugger = Debugger::listen("127.0.0.1:0").unwrap();
+        let address = debugger.local_addr();
+
+        let mut program = Program::new("", &[104, 1, 104, 2, 99]);
+        program.attach_debugger(debugger);
+
+        let handle = thread::spawn(move || {
+            let mut io = BasicProgramIO::new(&[]);
+            program.run(&mut io).unwrap();
+            io.outputs().to_vec()
+        });
+
+        let mut client = Client::connect(address);
+        assert_eq!(client.receive(), "stopped 0");
+        assert_eq!(client.send("break 2"), "ok");
+        assert_eq!(client.send("jump 2"), "error unknown command jump 2");
+        drop(client);
+
+        assert_eq!(handle.join().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_debugger_commands_while_paused() {
+        let debugger = Debugger::listen("127.0.0.1:0").unwrap();
+        let address = debugger.local_addr();
+
+        let mut program = Program::new("", &[104, 1, 99]);
+        program.attach_debugger(debugger);
+
+        let handle = thread::spawn(move || {
+            let mut io = BasicProgramIO::new(&[]);
+            program.run(&mut io).unwrap();
+            io.outputs().to_vec()
+        });
+
+        let mut client = Client::connect(address);
+        assert_eq!(client.receive(), "stopped 0");
+        // Already paused, so nothing runs
+        assert_eq!(client.send("pause"), "ok");
+        assert_eq!(client.send("registers"), "ok pc=0 rb=0");
+        assert_eq!(
+            client.send("read 1 9223372036854775807"),
+            "error 1 plus 9223372036854775807 cells is outside of memory"
+        );
+        assert_eq!(
+            client.send("write 9223372036854775807 1"),
+            "error 9223372036854775807 plus 1 cells is outside of memory"
+        );
+        assert_eq!(client.send("continue"), "ok");
+        assert_eq!(client.receive(), "halted 2");
+
+        assert_eq!(handle.join().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_debugger_rewrites_step_with() {
+        let debugger = Debugger::listen("127.0.0.1:0").unwrap();
+        let address = debugger.local_addr();
+
+        let mut program = Program::new("", &[1101, 7, 0, 9, 4, 9, 99, 0, 0, 0]);
+        program.expand_to(12).unwrap();
+        program.enable_sanitizer();
+        program.attach_debugger(debugger);
+
+        let handle = thread::spawn(move || {
+            let mut inputs = vec![42].into_iter();
+            let mut outputs = Vec::new();
+
+            loop {
+                match program.step_with(|| inputs.next()).unwrap() {
+                    Step::Output(output) => outputs.push(output),
+                    Step::Halted => break,
+                    Step::Continue | Step::NeedsInput => {}
+                }
+            }
+
+            (outputs, program.sanitizer_reports().to_vec())
+        });
+
+        let mut client = Client::connect(address);
+        assert_eq!(client.receive(), "stopped 0");
+        // Turn the paused add into an input, then output it and cell 11
+        assert_eq!(client.send("write 0 3 9 4 9 4 11 99"), "ok");
+        assert_eq!(client.send("write 11 5"), "ok");
+        assert_eq!(client.send("continue"), "ok");
+        assert_eq!(client.receive(), "halted 6");
+
+        assert_eq!(handle.join().unwrap(), (vec![42, 5], vec![]));
+    }
+}
@@ -0,0 +1,118 @@
+use std::error::Error;
+use std::fmt;
+
+use super::isa::InstructionSet;
+use super::opcode::OpCode;
+use super::parameters::ParameterMode;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Errors raised while running an intcode program
+pub enum IntcodeError {
+    /// The program asked for input but none was available
+    InputExhausted { address: i64 },
+    /// The program asked for input but the machine feeding it has stopped
+    ChannelClosed { address: i64 },
+    /// The program produced output but nothing is listening for it
+    OutputDisconnected { address: i64 },
+    /// The value at `address` is not a known opcode
+    UnknownOpCode { address: i64, opcode: i64 },
+    /// The instruction at `address` has a parameter mode that isn't 0, 1 or 2
+    UnknownParameterMode { address: i64, opcode: i64 },
+    /// The instruction at `address` has a negative position mode parameter
+    NegativeAddress { address: i64, value: i64 },
+    /// `address` is outside of memory
+    AddressOutOfRange { address: i64 },
+    /// The instruction at `address` writes to an immediate mode parameter
+    ImmediateDestination { address: i64 },
+    /// The instruction at `address` is newer than the program's instruction set
+    UnsupportedOpCode {
+        address: i64,
+        operation: OpCode,
+        instruction_set: InstructionSet,
+    },
+    /// The instruction at `address` uses a parameter mode newer than the program's instruction set
+    UnsupportedParameterMode {
+        address: i64,
+        mode: ParameterMode,
+        instruction_set: InstructionSet,
+    },
+    /// Every machine that has not halted is waiting on input
+    Deadlock { blocked: Vec<usize> },
+    /// Memory can't be expanded to `size` cells, since it already has `required`
+    MemoryTooSmall { size: i64, required: i64 },
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::InputExhausted { address } => {
+                write!(f, "Input requested at #{} but none was available", address)
+            }
+            IntcodeError::ChannelClosed { address } => {
+                write!(
+                    f,
+                    "Input requested at #{} but the channel is closed",
+                    address
+                )
+            }
+            IntcodeError::OutputDisconnected { address } => {
+                write!(f, "Output at #{} has no receiver", address)
+            }
+            IntcodeError::UnknownOpCode { address, opcode } => {
+                write!(f, "Unknown opcode {} at #{}", opcode, address)
+            }
+            IntcodeError::UnknownParameterMode { address, opcode } => {
+                write!(f, "Unknown parameter mode in {} at #{}", opcode, address)
+            }
+            IntcodeError::NegativeAddress { address, value } => {
+                write!(
+                    f,
+                    "Negative address {} in instruction at #{}",
+                    value, address
+                )
+            }
+            IntcodeError::AddressOutOfRange { address } => {
+                write!(f, "Address #{} is outside of memory", address)
+            }
+            IntcodeError::ImmediateDestination { address } => {
+                write!(
+                    f,
+                    "Instruction at #{} writes to an immediate value",
+                    address
+                )
+            }
+            IntcodeError::UnsupportedOpCode {
+                address,
+                operation,
+                instruction_set,
+            } => write!(
+                f,
+                "{:?} at #{} is not part of the {} instruction set",
+                operation, address, instruction_set
+            ),
+            IntcodeError::UnsupportedParameterMode {
+                address,
+                mode,
+                instruction_set,
+            } => write!(
+                f,
+                "{:?} parameter at #{} is not part of the {} instruction set",
+                mode, address, instruction_set
+            ),
+            IntcodeError::Deadlock { blocked } => {
+                write!(
+                    f,
+                    "Deadlock, machines {:?} are all waiting on input",
+                    blocked
+                )
+            }
+            IntcodeError::MemoryTooSmall { size, required } => write!(
+                f,
+                "Memory size {} is smaller than the {} cells already in use",
+                size, required
+            ),
+        }
+    }
+}
+
+impl Error for IntcodeError {}
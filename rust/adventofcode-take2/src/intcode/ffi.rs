@@ -0,0 +1,347 @@
+//! C interface to the VM
+//!
+//! Every function takes the machine returned by `intcode_create`. Null
+//! machines are treated as errors rather than crashing, and a panic inside
+//! the VM is caught and reported as `IntcodeStatus::Error`.
+//!
+//! Building the crate produces `libintcode` as a shared library, to be
+//! used with the checked in `include/intcode.h`:
+//!
+//! ```text
+//! cc -Iinclude tool.c -Ltarget/release -lintcode
+//! ```
+//!
+//! After changing this file, regenerate the header with
+//! `cargo build --features header`.
+
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::{Program, Step};
+
+/// A program with its queued inputs and outputs
+pub struct IntcodeMachine {
+    program: Program,
+    inputs: VecDeque<i64>,
+    outputs: VecDeque<i64>,
+    error: Option<CString>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Why `intcode_run` returned
+pub enum IntcodeStatus {
+    /// An output was queued, call `intcode_pop_output` to take it
+    Output = 0,
+    /// The program needs input, call `intcode_push_input` and run again
+    NeedsInput = 1,
+    /// The program has halted
+    Halted = 2,
+    /// The program failed, see `intcode_last_error`
+    Error = 3,
+}
+
+impl IntcodeMachine {
+    fn run(&mut self) -> IntcodeStatus {
+        let inputs = &mut self.inputs;
+
+        loop {
+            match self.program.step_with(|| inputs.pop_front()) {
+                Ok(Step::Continue) => {}
+                Ok(Step::Output(output)) => {
+                    self.outputs.push_back(output);
+                    return IntcodeStatus::Output;
+                }
+                Ok(Step::NeedsInput) => return IntcodeStatus::NeedsInput,
+                Ok(Step::Halted) => return IntcodeStatus::Halted,
+                Err(e) => {
+                    self.set_error(&e.to_string());
+                    return IntcodeStatus::Error;
+                }
+            }
+        }
+    }
+
+    /// Call `f`, reporting a panic inside it as an error
+    fn guard(&mut self, f: impl FnOnce(&mut Self) -> IntcodeStatus) -> IntcodeStatus {
+        match catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(status) => status,
+            Err(_) => {
+                self.set_error("The VM panicked");
+                IntcodeStatus::Error
+            }
+        }
+    }
+
+    fn set_error(&mut self, message: &str) {
+        // Interior nul bytes can't happen in our messages, but don't lose the
+        // error if one does
+        self.error = Some(CString::new(message.replace('\0', " ")).unwrap_or_default());
+    }
+}
+
+/// Create a machine from `len` values at `code`
+///
+/// `code` is copied and may be freed once this returns. The machine must be
+/// released with `intcode_free`.
+///
+/// # Safety
+///
+/// `code` must point to `len` readable values, or be null if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_create(code: *const i64, len: usize) -> *mut IntcodeMachine {
+    let code = if len == 0 {
+        &[]
+    } else if code.is_null() {
+        return ptr::null_mut();
+    } else {
+        std::slice::from_raw_parts(code, len)
+    };
+
+    Box::into_raw(Box::new(IntcodeMachine {
+        program: Program::new("ffi", code),
+        inputs: VecDeque::new(),
+        outputs: VecDeque::new(),
+        error: None,
+    }))
+}
+
+/// Grow memory to `size` values, filling new cells with zeros
+///
+/// Returns false, leaving memory alone, if `size` is smaller than memory already is.
+///
+/// # Safety
+///
+/// `machine` must be null or a live machine from `intcode_create`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_expand(machine: *mut IntcodeMachine, size: i64) -> bool {
+    match machine.as_mut() {
+        Some(machine) => machine.program.expand_to(size).is_ok(),
+        None => false,
+    }
+}
+
+/// Queue `value` to be read by the program's next input instruction
+///
+/// # Safety
+///
+/// `machine` must be null or a live machine from `intcode_create`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_push_input(machine: *mut IntcodeMachine, value: i64) -> bool {
+    match machine.as_mut() {
+        Some(machine) => {
+            machine.inputs.push_back(value);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Run until the program outputs a value, needs input or halts
+///
+/// # Safety
+///
+/// `machine` must be null or a live machine from `intcode_create`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_run(machine: *mut IntcodeMachine) -> IntcodeStatus {
+    let machine = match machine.as_mut() {
+        Some(machine) => machine,
+        None => return IntcodeStatus::Error,
+    };
+
+    machine.guard(IntcodeMachine::run)
+}
+
+/// Take the oldest queued output, returning false if there are none
+///
+/// # Safety
+///
+/// `machine` must be null or a live machine from `intcode_create`, and
+/// `value` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_pop_output(machine: *mut IntcodeMachine, value: *mut i64) -> bool {
+    match (machine.as_mut(), value.is_null()) {
+        (Some(machine), false) => match machine.outputs.pop_front() {
+            Some(output) => {
+                *value = output;
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Read memory at `address`, returning false if it is outside memory
+///
+/// # Safety
+///
+/// `machine` must be null or a live machine from `intcode_create`, and
+/// `value` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_read(
+    machine: *const IntcodeMachine,
+    address: i64,
+    value: *mut i64,
+) -> bool {
+    match (machine.as_ref(), value.is_null()) {
+        (Some(machine), false) if (0..machine.program.memory_size()).contains(&address) => {
+            *value = machine.program.read(address);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Write memory at `address`, returning false if it is outside memory
+///
+/// # Safety
+///
+/// `machine` must be null or a live machine from `intcode_create`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_write(
+    machine: *mut IntcodeMachine,
+    address: i64,
+    value: i64,
+) -> bool {
+    match machine.as_mut() {
+        Some(machine) if (0..machine.program.memory_size()).contains(&address) => {
+            machine.program.write(address, value);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// The message for the last `IntcodeStatus::Error`, or null if there wasn't one
+///
+/// The string belongs to the machine and is valid until the next call that
+/// fails or `intcode_free`.
+///
+/// # Safety
+///
+/// `machine` must be null or a live machine from `intcode_create`.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_last_error(machine: *const IntcodeMachine) -> *const c_char {
+    machine
+        .as_ref()
+        .and_then(|machine| machine.error.as_ref())
+        .map_or(ptr::null(), |error| error.as_ptr())
+}
+
+/// Release a machine from `intcode_create`
+///
+/// # Safety
+///
+/// `machine` must be null or a live machine from `intcode_create`, and must
+/// not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_free(machine: *mut IntcodeMachine) {
+    if !machine.is_null() {
+        drop(Box::from_raw(machine));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_ffi_run() {
+        let code = [3, 11, 3, 12, 2, 11, 12, 13, 4, 13, 99, 0, 0, 0];
+
+        unsafe {
+            let machine = intcode_create(code.as_ptr(), code.len());
+            let mut output = 0;
+
+            assert_eq!(intcode_run(machine), IntcodeStatus::NeedsInput);
+            assert!(intcode_push_input(machine, 3));
+            assert!(intcode_push_input(machine, 4));
+            assert_eq!(intcode_run(machine), IntcodeStatus::Output);
+            assert!(intcode_pop_output(machine, &mut output));
+            assert_eq!(output, 12);
+            assert!(!intcode_pop_output(machine, &mut output));
+            assert_eq!(intcode_run(machine), IntcodeStatus::Halted);
+
+            intcode_free(machine);
+        }
+    }
+
+    #[test]
+    fn test_ffi_memory() {
+        let code = [1, 0, 0, 0, 99];
+
+        unsafe {
+            let machine = intcode_create(code.as_ptr(), code.len());
+            let mut value = 0;
+
+            assert!(intcode_write(machine, 1, 4));
+            assert!(!intcode_write(machine, 5, 1));
+            assert_eq!(intcode_run(machine), IntcodeStatus::Halted);
+            assert!(intcode_read(machine, 0, &mut value));
+            assert_eq!(value, 100);
+            assert!(!intcode_read(machine, -1, &mut value));
+
+            assert!(intcode_expand(machine, 10));
+            assert!(intcode_read(machine, 9, &mut value));
+            assert_eq!(value, 0);
+
+            intcode_free(machine);
+        }
+    }
+
+    #[test]
+    fn test_ffi_errors() {
+        let code = [42];
+
+        unsafe {
+            let machine = intcode_create(code.as_ptr(), code.len());
+
+            assert!(intcode_last_error(machine).is_null());
+            assert_eq!(intcode_run(machine), IntcodeStatus::Error);
+            assert_eq!(
+                CStr::from_ptr(intcode_last_error(machine)).to_str(),
+                Ok("Unknown opcode 42 at #0")
+            );
+            intcode_free(machine);
+
+            // Writes past the end of memory are errors too
+            let code = [1101, 1, 1, 100, 99];
+            let machine = intcode_create(code.as_ptr(), code.len());
+            assert_eq!(intcode_run(machine), IntcodeStatus::Error);
+            assert_eq!(
+                CStr::from_ptr(intcode_last_error(machine)).to_str(),
+                Ok("Address #100 is outside of memory")
+            );
+            intcode_free(machine);
+
+            assert_eq!(intcode_run(ptr::null_mut()), IntcodeStatus::Error);
+            assert!(!intcode_push_input(ptr::null_mut(), 1));
+            intcode_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_ffi_panic() {
+        let code = [99];
+
+        unsafe {
+            let machine = intcode_create(code.as_ptr(), code.len());
+
+            let status = (*machine).guard(|_| panic!("Broken VM"));
+            assert_eq!(status, IntcodeStatus::Error);
+            assert_eq!(
+                CStr::from_ptr(intcode_last_error(machine)).to_str(),
+                Ok("The VM panicked")
+            );
+
+            // The machine is still usable afterwards
+            assert_eq!(intcode_run(machine), IntcodeStatus::Halted);
+            intcode_free(machine);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use super::io::ProgramIO;
+
+/// Receives decoded frames from a `FramedIO` and supplies its inputs
+pub trait FrameHandler<M> {
+    fn get_next_input(&mut self) -> i64;
+    fn receive_message(&mut self, message: M);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A frame that could not be delivered
+pub enum FrameError<E> {
+    /// The frame was complete but could not be decoded
+    Decode { frame: Vec<i64>, error: E },
+    /// The program stopped partway through a frame
+    Incomplete { values: Vec<i64> },
+}
+
+impl<E: fmt::Display> fmt::Display for FrameError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameError::Decode { frame, error } => {
+                write!(f, "Unable to decode frame {:?}: {}", frame, error)
+            }
+            FrameError::Incomplete { values } => write!(f, "Incomplete frame {:?}", values),
+        }
+    }
+}
+
+impl<E: Error> Error for FrameError<E> {}
+
+/// Groups outputs into frames of `N` values and decodes each into `M`
+///
+/// Decoding stops at the first malformed frame. The error, or a trailing
+/// partial frame, is reported by `finish`.
+pub struct FramedIO<M, H, const N: usize>
+where
+    M: TryFrom<[i64; N]>,
+    H: FrameHandler<M>,
+{
+    handler: H,
+    frame: [i64; N],
+    filled: usize,
+    error: Option<FrameError<M::Error>>,
+}
+
+impl<M, H, const N: usize> FramedIO<M, H, N>
+where
+    M: TryFrom<[i64; N]>,
+    H: FrameHandler<M>,
+{
+    pub fn new(handler: H) -> FramedIO<M, H, N> {
+        FramedIO {
+            handler,
+            frame: [0; N],
+            filled: 0,
+            error: None,
+        }
+    }
+
+    /// Consume the IO, returning the handler if every frame was delivered
+    pub fn finish(self) -> Result<H, FrameError<M::Error>> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        if self.filled != 0 {
+            return Err(FrameError::Incomplete {
+                values: self.frame[..self.filled].to_vec(),
+            });
+        }
+
+        Ok(self.handler)
+    }
+}
+
+impl<M, H, const N: usize> ProgramIO for FramedIO<M, H, N>
+where
+    M: TryFrom<[i64; N]>,
+    H: FrameHandler<M>,
+{
+    fn get_next_input(&mut self) -> i64 {
+        self.handler.get_next_input()
+    }
+
+    fn receive_output(&mut self, output: i64) {
+        if self.error.is_some() {
+            return;
+        }
+
+        self.frame[self.filled] = output;
+        self.filled += 1;
+
+        if self.filled < N {
+            return;
+        }
+
+        self.filled = 0;
+
+        match M::try_from(self.frame) {
+            Ok(message) => self.handler.receive_message(message),
+            Err(error) => {
+                self.error = Some(FrameError::Decode {
+                    frame: self.frame.to_vec(),
+                    error,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Program;
+
+    #[derive(Debug, PartialEq)]
+    struct Tile {
+        x: i64,
+        y: i64,
+        id: i64,
+    }
+
+    impl TryFrom<[i64; 3]> for Tile {
+        type Error = String;
+
+        fn try_from(frame: [i64; 3]) -> Result<Self, Self::Error> {
+            if frame[2] < 0 {
+                return Err(format!("bad tile id {}", frame[2]));
+            }
+
+            Ok(Tile {
+                x: frame[0],
+                y: frame[1],
+                id: frame[2],
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct Tiles(Vec<Tile>);
+
+    impl FrameHandler<Tile> for Tiles {
+        fn get_next_input(&mut self) -> i64 {
+            0
+        }
+
+        fn receive_message(&mut self, message: Tile) {
+            self.0.push(message);
+        }
+    }
+
+    fn run(code: &[i64]) -> Result<Tiles, FrameError<String>> {
+        let mut io: FramedIO<Tile, Tiles, 3> = FramedIO::new(Tiles::default());
+        Program::new("", code).run(&mut io).unwrap();
+        io.finish()
+    }
+
+    #[test]
+    fn test_framed_io() {
+        let tiles = run(&[104, 1, 104, 2, 104, 3, 104, 4, 104, 5, 104, 6, 99]).unwrap();
+
+        assert_eq!(
+            tiles.0,
+            vec![Tile { x: 1, y: 2, id: 3 }, Tile { x: 4, y: 5, id: 6 }]
+        );
+    }
+
+    #[test]
+    fn test_framed_io_decode_error() {
+        assert_eq!(
+            run(&[104, 1, 104, 2, 104, -3, 99]).err(),
+            Some(FrameError::Decode {
+                frame: vec![1, 2, -3],
+                error: "bad tile id -3".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_framed_io_incomplete() {
+        assert_eq!(
+            run(&[104, 1, 104, 2, 104, 3, 104, 4, 99]).err(),
+            Some(FrameError::Incomplete { values: vec![4] })
+        );
+    }
+}
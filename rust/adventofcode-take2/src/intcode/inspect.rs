@@ -0,0 +1,235 @@
+use std::fmt;
+use std::ops::Range;
+
+use super::instruction::Instruction;
+use super::Program;
+
+#[derive(Debug, Clone, PartialEq)]
+/// What a memory cell looks like to a linear disassembly
+pub enum Annotation {
+    /// The start of an instruction, decoded
+    Instruction(String),
+    /// A parameter of the instruction before it
+    Operand,
+    /// Anything that doesn't decode as an instruction
+    Data,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A single memory cell in a dump
+pub struct DumpRow {
+    pub address: i64,
+    pub value: i64,
+    pub annotation: Annotation,
+}
+
+impl fmt::Display for DumpRow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>8} {:>16}", self.address, self.value)?;
+
+        match &self.annotation {
+            Annotation::Instruction(instruction) => write!(f, "  {}", instruction),
+            Annotation::Operand | Annotation::Data => Ok(()),
+        }
+    }
+}
+
+/// Dump the cells in `range`, decoding instructions with a linear sweep
+///
+/// Decoding starts fresh at `range.start`, so starting partway through an
+/// instruction will misread it.
+pub fn dump(program: &Program, range: Range<i64>) -> Vec<DumpRow> {
+    let end = range.end.min(program.memory_size());
+    let mut rows = Vec::new();
+    let mut address = range.start.max(0);
+
+    while address < end {
+        match Instruction::new(address, &program.memory) {
+            Ok((instruction, size)) => {
+                rows.push(DumpRow {
+                    address,
+                    value: program.read(address),
+                    annotation: Annotation::Instruction(instruction.to_string()),
+                });
+
+                for operand in (address + 1)..(address + size).min(end) {
+                    rows.push(DumpRow {
+                        address: operand,
+                        value: program.read(operand),
+                        annotation: Annotation::Operand,
+                    });
+                }
+
+                address += size;
+            }
+            Err(_) => {
+                rows.push(DumpRow {
+                    address,
+                    value: program.read(address),
+                    annotation: Annotation::Data,
+                });
+
+                address += 1;
+            }
+        }
+    }
+
+    rows
+}
+
+/// Render rows from `dump` as a table
+pub fn render_dump(rows: &[DumpRow]) -> String {
+    let mut table = format!("{:>8} {:>16}  {}\n", "address", "value", "instruction");
+
+    for row in rows {
+        table.push_str(&row.to_string());
+        table.push('\n');
+    }
+
+    table
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A run of consecutive cells that differ between two snapshots
+pub struct ChangedRange {
+    pub start: i64,
+    pub before: Vec<i64>,
+    pub after: Vec<i64>,
+}
+
+impl fmt::Display for ChangedRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |values: &[i64]| {
+            values
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        if self.before.len() == 1 {
+            write!(f, "#{}", self.start)?;
+        } else {
+            write!(
+                f,
+                "#{}..#{}",
+                self.start,
+                self.start + self.before.len() as i64 - 1
+            )?;
+        }
+
+        write!(f, ": {} -> {}", join(&self.before), join(&self.after))
+    }
+}
+
+/// Find every cell that differs between two memory snapshots
+///
+/// Cells missing from the shorter snapshot count as zero, like unexpanded
+/// memory.
+pub fn diff(before: &[i64], after: &[i64]) -> Vec<ChangedRange> {
+    let mut ranges: Vec<ChangedRange> = Vec::new();
+    let cell = |memory: &[i64], address: usize| memory.get(address).copied().unwrap_or(0);
+
+    for address in 0..before.len().max(after.len()) {
+        let (old, new) = (cell(before, address), cell(after, address));
+
+        if old == new {
+            continue;
+        }
+
+        match ranges.last_mut() {
+            Some(range) if range.start + range.before.len() as i64 == address as i64 => {
+                range.before.push(old);
+                range.after.push(new);
+            }
+            _ => ranges.push(ChangedRange {
+                start: address as i64,
+                before: vec![old],
+                after: vec![new],
+            }),
+        }
+    }
+
+    ranges
+}
+
+/// Render ranges from `diff`, one per line
+pub fn render_diff(ranges: &[ChangedRange]) -> String {
+    ranges
+        .iter()
+        .map(|r| r.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dump() {
+        let program = Program::new("", &[1002, 4, 3, 4, 33, -7]);
+        let rows = dump(&program, 0..10);
+
+        assert_eq!(rows.len(), 6);
+        assert_eq!(
+            rows[0].annotation,
+            Annotation::Instruction("(#4 * \"3\" => #4)".to_string())
+        );
+        assert_eq!(rows[1].annotation, Annotation::Operand);
+        assert_eq!(rows[3].annotation, Annotation::Operand);
+        assert_eq!(rows[4].annotation, Annotation::Data);
+        assert_eq!(rows[5].annotation, Annotation::Data);
+        assert_eq!(rows[5].value, -7);
+    }
+
+    #[test]
+    fn test_dump_partial_range() {
+        let program = Program::new("", &[1, 0, 0, 0, 99]);
+        let rows = dump(&program, 4..5);
+
+        assert_eq!(
+            rows,
+            vec![DumpRow {
+                address: 4,
+                value: 99,
+                annotation: Annotation::Instruction("(Halt!)".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let ranges = diff(&[1, 2, 3, 4, 5], &[1, 9, 9, 4, 6, 7]);
+
+        assert_eq!(
+            ranges,
+            vec![
+                ChangedRange {
+                    start: 1,
+                    before: vec![2, 3],
+                    after: vec![9, 9],
+                },
+                ChangedRange {
+                    start: 4,
+                    before: vec![5, 0],
+                    after: vec![6, 7],
+                },
+            ]
+        );
+        assert_eq!(
+            render_diff(&ranges),
+            "#1..#2: 2,3 -> 9,9\n#4..#5: 5,0 -> 6,7"
+        );
+    }
+
+    #[test]
+    fn test_diff_after_run() {
+        let mut program = Program::new("", &[1, 0, 0, 0, 99]);
+        let before = program.dump();
+
+        program.outputs(vec![]).for_each(drop);
+
+        assert_eq!(render_diff(&diff(&before, &program.dump())), "#0: 1 -> 2");
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use super::error::IntcodeError;
 use super::io::ProgramIO;
 use super::memory::ProgramMemory;
 use super::opcode::{ExecutionState, OpCode};
@@ -26,20 +27,25 @@ impl Default for Instruction {
 }
 
 impl Instruction {
-    pub fn new(address: i64, memory: &ProgramMemory) -> (Instruction, i64) {
-        let opcode = memory[address];
-
-        match opcode % 100 {
-            1 => (Self::binary(OpCode::Add, address, opcode, memory), 4),
-            2 => (Self::binary(OpCode::Multiply, address, opcode, memory), 4),
-            3 => (Self::nullary(OpCode::Input, address, opcode, memory), 2),
-            4 => (Self::nullary(OpCode::Output, address, opcode, memory), 2),
-            5 => (Self::unary(OpCode::JumpIfTrue, address, opcode, memory), 3),
-            6 => (Self::unary(OpCode::JumpIfFalse, address, opcode, memory), 3),
-            7 => (Self::binary(OpCode::LessThan, address, opcode, memory), 4),
-            8 => (Self::binary(OpCode::Equals, address, opcode, memory), 4),
+    pub fn new(address: i64, memory: &ProgramMemory) -> Result<(Instruction, i64), IntcodeError> {
+        let opcode = memory
+            .get(address)
+            .ok_or(IntcodeError::AddressOutOfRange { address })?;
+
+        Ok(match opcode % 100 {
+            1 => (Self::binary(OpCode::Add, address, opcode, memory)?, 4),
+            2 => (Self::binary(OpCode::Multiply, address, opcode, memory)?, 4),
+            3 => (Self::nullary(OpCode::Input, address, opcode, memory)?, 2),
+            4 => (Self::nullary(OpCode::Output, address, opcode, memory)?, 2),
+            5 => (Self::unary(OpCode::JumpIfTrue, address, opcode, memory)?, 3),
+            6 => (
+                Self::unary(OpCode::JumpIfFalse, address, opcode, memory)?,
+                3,
+            ),
+            7 => (Self::binary(OpCode::LessThan, address, opcode, memory)?, 4),
+            8 => (Self::binary(OpCode::Equals, address, opcode, memory)?, 4),
             9 => (
-                Self::nullary(OpCode::AdjustRelative, address, opcode, memory),
+                Self::nullary(OpCode::AdjustRelative, address, opcode, memory)?,
                 2,
             ),
             99 => (
@@ -51,67 +57,102 @@ impl Instruction {
                 },
                 1,
             ),
-            _ => {
-                panic!("Unknown opcode");
-            }
-        }
+            _ => return Err(IntcodeError::UnknownOpCode { address, opcode }),
+        })
+    }
+
+    pub fn operation(&self) -> OpCode {
+        self.operation
+    }
+
+    pub fn modes(&self) -> impl Iterator<Item = ParameterMode> {
+        vec![self.param1, self.param2, self.param3]
+            .into_iter()
+            .flatten()
+            .map(|p| p.mode())
     }
 
-    pub fn execute(&self, memory: &mut ProgramMemory, io: &mut impl ProgramIO) -> ExecutionState {
+    /// Parameters whose values the instruction reads
+    pub fn sources(&self) -> impl Iterator<Item = Parameter> {
+        let sources = match self.operation {
+            OpCode::Add
+            | OpCode::Multiply
+            | OpCode::LessThan
+            | OpCode::Equals
+            | OpCode::JumpIfTrue
+            | OpCode::JumpIfFalse => vec![self.param1, self.param2],
+            OpCode::Output | OpCode::AdjustRelative => vec![self.param1],
+            OpCode::Input | OpCode::Halt => vec![],
+        };
+
+        sources.into_iter().flatten()
+    }
+
+    /// The parameter the instruction writes its result to
+    pub fn destination(&self) -> Option<Parameter> {
         match self.operation {
+            OpCode::Add | OpCode::Multiply | OpCode::LessThan | OpCode::Equals => self.param3,
+            OpCode::Input => self.param1,
+            _ => None,
+        }
+    }
+
+    pub fn execute(
+        &self,
+        memory: &mut ProgramMemory,
+        io: &mut impl ProgramIO,
+    ) -> Result<ExecutionState, IntcodeError> {
+        Ok(match self.operation {
             OpCode::Add => {
-                *memory.address_mut(self.param3) =
-                    memory.value(self.param1) + memory.value(self.param2);
+                let value = memory.value(self.param1)? + memory.value(self.param2)?;
+                *memory.address_mut(self.param3)? = value;
                 ExecutionState::Continue
             }
             OpCode::Multiply => {
-                *memory.address_mut(self.param3) =
-                    memory.value(self.param1) * memory.value(self.param2);
+                let value = memory.value(self.param1)? * memory.value(self.param2)?;
+                *memory.address_mut(self.param3)? = value;
                 ExecutionState::Continue
             }
             OpCode::Input => {
-                *memory.address_mut(self.param1) = io.get_next_input();
+                // Check the destination before taking the input, so it isn't lost
+                memory.address_mut(self.param1)?;
+                let value = io.get_next_input();
+                io.check(memory.current_address())?;
+                *memory.address_mut(self.param1)? = value;
                 ExecutionState::Continue
             }
             OpCode::Output => {
-                io.receive_output(memory.value(self.param1));
+                io.receive_output(memory.value(self.param1)?);
+                io.check(memory.current_address())?;
                 ExecutionState::Continue
             }
             OpCode::JumpIfTrue => {
-                if memory.value(self.param1) != 0 {
-                    ExecutionState::Jump(memory.value(self.param2))
+                if memory.value(self.param1)? != 0 {
+                    ExecutionState::Jump(memory.value(self.param2)?)
                 } else {
                     ExecutionState::Continue
                 }
             }
             OpCode::JumpIfFalse => {
-                if memory.value(self.param1) == 0 {
-                    ExecutionState::Jump(memory.value(self.param2))
+                if memory.value(self.param1)? == 0 {
+                    ExecutionState::Jump(memory.value(self.param2)?)
                 } else {
                     ExecutionState::Continue
                 }
             }
             OpCode::LessThan => {
-                *memory.address_mut(self.param3) =
-                    if memory.value(self.param1) < memory.value(self.param2) {
-                        1
-                    } else {
-                        0
-                    };
+                let value = memory.value(self.param1)? < memory.value(self.param2)?;
+                *memory.address_mut(self.param3)? = value as i64;
                 ExecutionState::Continue
             }
             OpCode::Equals => {
-                *memory.address_mut(self.param3) =
-                    if memory.value(self.param1) == memory.value(self.param2) {
-                        1
-                    } else {
-                        0
-                    };
+                let value = memory.value(self.param1)? == memory.value(self.param2)?;
+                *memory.address_mut(self.param3)? = value as i64;
                 ExecutionState::Continue
             }
-            OpCode::AdjustRelative => ExecutionState::AdjustRelative(memory.value(self.param1)),
+            OpCode::AdjustRelative => ExecutionState::AdjustRelative(memory.value(self.param1)?),
             OpCode::Halt => ExecutionState::Halt,
-        }
+        })
     }
 
     fn create_parameter(
@@ -119,11 +160,20 @@ impl Instruction {
         address: i64,
         opcode: i64,
         memory: &ProgramMemory,
-    ) -> Parameter {
-        Parameter::new(
-            ParameterMode::from_opcode(opcode as i64, index),
-            memory[(address + index + 1)],
-        )
+    ) -> Result<Parameter, IntcodeError> {
+        let mode = ParameterMode::from_opcode(opcode, index)
+            .ok_or(IntcodeError::UnknownParameterMode { address, opcode })?;
+        let value = memory
+            .get(address + index + 1)
+            .ok_or(IntcodeError::AddressOutOfRange {
+                address: address + index + 1,
+            })?;
+
+        if mode == ParameterMode::Address && value < 0 {
+            return Err(IntcodeError::NegativeAddress { address, value });
+        }
+
+        Ok(Parameter::new(mode, value))
     }
 
     fn nullary(
@@ -131,31 +181,41 @@ impl Instruction {
         address: i64,
         opcode: i64,
         memory: &ProgramMemory,
-    ) -> Instruction {
-        Instruction {
+    ) -> Result<Instruction, IntcodeError> {
+        Ok(Instruction {
             operation,
-            param1: Some(Instruction::create_parameter(0, address, opcode, memory)),
+            param1: Some(Instruction::create_parameter(0, address, opcode, memory)?),
             param2: None,
             param3: None,
-        }
+        })
     }
 
-    fn unary(operation: OpCode, address: i64, opcode: i64, memory: &ProgramMemory) -> Instruction {
-        Instruction {
+    fn unary(
+        operation: OpCode,
+        address: i64,
+        opcode: i64,
+        memory: &ProgramMemory,
+    ) -> Result<Instruction, IntcodeError> {
+        Ok(Instruction {
             operation,
-            param1: Some(Instruction::create_parameter(0, address, opcode, memory)),
-            param2: Some(Instruction::create_parameter(1, address, opcode, memory)),
+            param1: Some(Instruction::create_parameter(0, address, opcode, memory)?),
+            param2: Some(Instruction::create_parameter(1, address, opcode, memory)?),
             param3: None,
-        }
+        })
     }
 
-    fn binary(operation: OpCode, address: i64, opcode: i64, memory: &ProgramMemory) -> Instruction {
-        Instruction {
+    fn binary(
+        operation: OpCode,
+        address: i64,
+        opcode: i64,
+        memory: &ProgramMemory,
+    ) -> Result<Instruction, IntcodeError> {
+        Ok(Instruction {
             operation,
-            param1: Some(Instruction::create_parameter(0, address, opcode, memory)),
-            param2: Some(Instruction::create_parameter(1, address, opcode, memory)),
-            param3: Some(Instruction::create_parameter(2, address, opcode, memory)),
-        }
+            param1: Some(Instruction::create_parameter(0, address, opcode, memory)?),
+            param2: Some(Instruction::create_parameter(1, address, opcode, memory)?),
+            param3: Some(Instruction::create_parameter(2, address, opcode, memory)?),
+        })
     }
 }
 
@@ -1,8 +1,40 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, SendError, Sender};
+
+use tracing::info;
+
+use super::error::IntcodeError;
+use super::{Program, Step};
+
 pub trait ProgramIO {
     fn get_next_input(&mut self) -> i64;
     fn receive_output(&mut self, output: i64);
+
+    /// Stop the program if the last input or output failed
+    ///
+    /// Called after each input and output instruction, at `address`. IO that
+    /// can fail, like a `ChannelIO` whose channel closed, returns the error
+    /// here instead of panicking.
+    fn check(&mut self, _address: i64) -> Result<(), IntcodeError> {
+        Ok(())
+    }
+}
+
+impl<T: ProgramIO + ?Sized> ProgramIO for &mut T {
+    fn get_next_input(&mut self) -> i64 {
+        (**self).get_next_input()
+    }
+
+    fn receive_output(&mut self, output: i64) {
+        (**self).receive_output(output)
+    }
+
+    fn check(&mut self, address: i64) -> Result<(), IntcodeError> {
+        (**self).check(address)
+    }
 }
 
+#[derive(Default)]
 pub struct BasicProgramIO {
     inputs: Vec<i64>,
     current_input: usize,
@@ -12,7 +44,7 @@ pub struct BasicProgramIO {
 impl BasicProgramIO {
     pub fn new(inputs: &[i64]) -> BasicProgramIO {
         BasicProgramIO {
-            inputs: inputs.iter().copied().collect(),
+            inputs: inputs.to_vec(),
             current_input: 0,
             outputs: Vec::new(),
         }
@@ -34,12 +66,481 @@ impl ProgramIO for BasicProgramIO {
     }
 }
 
-impl Default for BasicProgramIO {
-    fn default() -> BasicProgramIO {
-        BasicProgramIO {
-            inputs: Vec::new(),
-            current_input: 0,
-            outputs: Vec::new(),
+/// Combinators available on every `ProgramIO`
+///
+/// Each adapter is itself a `ProgramIO`, so they can be stacked in any order:
+/// `io.chain_inputs(&[5]).tee().logged("amp")`
+pub trait ProgramIOExt: ProgramIO + Sized {
+    /// Record all traffic passing through
+    fn tee(self) -> TeeIO<Self> {
+        TeeIO::new(self)
+    }
+
+    /// Provide `prefix` as inputs before asking the wrapped IO
+    fn chain_inputs(self, prefix: &[i64]) -> ChainedIO<Self> {
+        ChainedIO::new(prefix, self)
+    }
+
+    /// Log all traffic under `name`
+    fn logged(self, name: &str) -> LoggingIO<Self> {
+        LoggingIO::new(name, self)
+    }
+}
+
+impl<T: ProgramIO> ProgramIOExt for T {}
+
+/// IO backed by a pair of closures
+pub struct FnIO<I, O>
+where
+    I: FnMut() -> i64,
+    O: FnMut(i64),
+{
+    input: I,
+    output: O,
+}
+
+impl<I, O> FnIO<I, O>
+where
+    I: FnMut() -> i64,
+    O: FnMut(i64),
+{
+    pub fn new(input: I, output: O) -> FnIO<I, O> {
+        FnIO { input, output }
+    }
+}
+
+impl<I, O> ProgramIO for FnIO<I, O>
+where
+    I: FnMut() -> i64,
+    O: FnMut(i64),
+{
+    fn get_next_input(&mut self) -> i64 {
+        (self.input)()
+    }
+
+    fn receive_output(&mut self, output: i64) {
+        (self.output)(output)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A single value passing through a `TeeIO`
+pub enum Traffic {
+    Input(i64),
+    Output(i64),
+}
+
+/// Records every input and output while forwarding them to the wrapped IO
+pub struct TeeIO<T: ProgramIO> {
+    inner: T,
+    traffic: Vec<Traffic>,
+}
+
+impl<T: ProgramIO> TeeIO<T> {
+    pub fn new(inner: T) -> TeeIO<T> {
+        TeeIO {
+            inner,
+            traffic: Vec::new(),
+        }
+    }
+
+    /// All traffic in the order it happened
+    pub fn traffic(&self) -> &[Traffic] {
+        &self.traffic
+    }
+
+    pub fn inputs(&self) -> Vec<i64> {
+        self.traffic
+            .iter()
+            .filter_map(|t| match t {
+                Traffic::Input(n) => Some(*n),
+                Traffic::Output(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn outputs(&self) -> Vec<i64> {
+        self.traffic
+            .iter()
+            .filter_map(|t| match t {
+                Traffic::Input(_) => None,
+                Traffic::Output(n) => Some(*n),
+            })
+            .collect()
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ProgramIO> ProgramIO for TeeIO<T> {
+    fn get_next_input(&mut self) -> i64 {
+        let input = self.inner.get_next_input();
+        self.traffic.push(Traffic::Input(input));
+        input
+    }
+
+    fn receive_output(&mut self, output: i64) {
+        self.traffic.push(Traffic::Output(output));
+        self.inner.receive_output(output);
+    }
+    fn check(&mut self, address: i64) -> Result<(), IntcodeError> {
+        self.inner.check(address)
+    }
+}
+
+/// Feeds a fixed list of inputs before falling through to the wrapped IO
+pub struct ChainedIO<T: ProgramIO> {
+    prefix: VecDeque<i64>,
+    inner: T,
+}
+
+impl<T: ProgramIO> ChainedIO<T> {
+    pub fn new(prefix: &[i64], inner: T) -> ChainedIO<T> {
+        ChainedIO {
+            prefix: prefix.iter().copied().collect(),
+            inner,
         }
     }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ProgramIO> ProgramIO for ChainedIO<T> {
+    fn get_next_input(&mut self) -> i64 {
+        match self.prefix.pop_front() {
+            Some(input) => input,
+            None => self.inner.get_next_input(),
+        }
+    }
+
+    fn receive_output(&mut self, output: i64) {
+        self.inner.receive_output(output);
+    }
+    fn check(&mut self, address: i64) -> Result<(), IntcodeError> {
+        self.inner.check(address)
+    }
+}
+
+/// Logs all traffic at info level before forwarding it to the wrapped IO
+pub struct LoggingIO<T: ProgramIO> {
+    name: String,
+    inner: T,
+}
+
+impl<T: ProgramIO> LoggingIO<T> {
+    pub fn new(name: &str, inner: T) -> LoggingIO<T> {
+        LoggingIO {
+            name: name.to_string(),
+            inner,
+        }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: ProgramIO> ProgramIO for LoggingIO<T> {
+    fn get_next_input(&mut self) -> i64 {
+        info!("{} - Waiting on input", self.name);
+        let input = self.inner.get_next_input();
+        info!("{} - Received input {}", self.name, input);
+        input
+    }
+
+    fn receive_output(&mut self, output: i64) {
+        info!("{} - Sending output {}", self.name, output);
+        self.inner.receive_output(output);
+    }
+    fn check(&mut self, address: i64) -> Result<(), IntcodeError> {
+        self.inner.check(address)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A message sent between machines connected by `ChannelIO`
+pub enum ChannelEvent {
+    Output(i64),
+    /// The sending machine has stopped and nothing more will follow
+    Halted,
+}
+
+/// Connects a program to channels so machines can run on separate threads
+///
+/// It can also be used as a `ProgramIO`, for example under the `ProgramIOExt`
+/// adapters, where closed channels stop the program with the same errors as
+/// `run`. No `ChannelEvent::Halted` is sent then, but dropping the IO closes
+/// the channel, which receivers treat the same.
+pub struct ChannelIO {
+    name: String,
+    pending: VecDeque<i64>,
+    input: Receiver<ChannelEvent>,
+    output: Sender<ChannelEvent>,
+    last_output: Option<i64>,
+    /// Why the last input or output failed, given the instruction's address
+    failure: Option<fn(i64) -> IntcodeError>,
+}
+
+impl ChannelIO {
+    /// Create an IO reading from `input` after `first_inputs` are used up
+    ///
+    /// Also returns the receiver for this machine's outputs
+    pub fn new(
+        name: &str,
+        first_inputs: &[i64],
+        input: Receiver<ChannelEvent>,
+    ) -> (ChannelIO, Receiver<ChannelEvent>) {
+        let (output, next_input) = channel();
+
+        (
+            ChannelIO {
+                name: name.to_string(),
+                pending: first_inputs.iter().copied().collect(),
+                input,
+                output,
+                last_output: None,
+                failure: None,
+            },
+            next_input,
+        )
+    }
+
+    /// The most recent value the program sent
+    pub fn last_output(&self) -> Option<i64> {
+        self.last_output
+    }
+
+    /// Run `program` until it stops, returning its final output
+    ///
+    /// `ChannelEvent::Halted` is always sent once the program stops, whether
+    /// it halted normally or hit an error.
+    pub fn run(&mut self, program: &mut Program) -> Result<Option<i64>, IntcodeError> {
+        let result = self.run_until_halted(program);
+
+        info!("{} - Halted", self.name);
+        // The receiver may already be gone, nothing is lost if so
+        let _ = self.output.send(ChannelEvent::Halted);
+
+        result.map(|_| self.last_output)
+    }
+
+    fn run_until_halted(&mut self, program: &mut Program) -> Result<(), IntcodeError> {
+        loop {
+            let address = program.current_address();
+
+            match program.step_with(|| self.next_input())? {
+                Step::Continue => {}
+                Step::Output(output) => {
+                    self.send_output(output)
+                        .map_err(|_| IntcodeError::OutputDisconnected { address })?;
+                }
+                Step::NeedsInput => return Err(IntcodeError::ChannelClosed { address }),
+                Step::Halted => return Ok(()),
+            }
+        }
+    }
+
+    fn send_output(&mut self, output: i64) -> Result<(), SendError<ChannelEvent>> {
+        info!("{} - Sending output {}", self.name, output);
+        self.last_output = Some(output);
+        self.output.send(ChannelEvent::Output(output))
+    }
+
+    fn next_input(&mut self) -> Option<i64> {
+        if let Some(input) = self.pending.pop_front() {
+            info!("{} - Using queued input {}", self.name, input);
+            return Some(input);
+        }
+
+        info!("{} - Waiting on input", self.name);
+        match self.input.recv() {
+            Ok(ChannelEvent::Output(input)) => {
+                info!("{} - Received input {}", self.name, input);
+                Some(input)
+            }
+            Ok(ChannelEvent::Halted) | Err(_) => None,
+        }
+    }
+}
+
+impl ProgramIO for ChannelIO {
+    fn get_next_input(&mut self) -> i64 {
+        self.next_input().unwrap_or_else(|| {
+            self.failure = Some(|address| IntcodeError::ChannelClosed { address });
+            0
+        })
+    }
+
+    fn receive_output(&mut self, output: i64) {
+        if self.send_output(output).is_err() {
+            self.failure = Some(|address| IntcodeError::OutputDisconnected { address });
+        }
+    }
+
+    fn check(&mut self, address: i64) -> Result<(), IntcodeError> {
+        match self.failure.take() {
+            Some(failure) => Err(failure(address)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Program;
+
+    #[test]
+    fn test_fn_io() {
+        let mut outputs = Vec::new();
+        let mut io = FnIO::new(|| 14, |n| outputs.push(n));
+
+        Program::new("", &[3, 0, 4, 0, 99]).run(&mut io).unwrap();
+
+        assert_eq!(outputs, &[14]);
+    }
+
+    #[test]
+    fn test_tee_io() {
+        let mut io = BasicProgramIO::new(&[3, 4]).tee();
+
+        Program::new("", &[3, 11, 3, 12, 2, 11, 12, 13, 4, 13, 99, 0, 0, 0])
+            .run(&mut io)
+            .unwrap();
+
+        assert_eq!(
+            io.traffic(),
+            &[Traffic::Input(3), Traffic::Input(4), Traffic::Output(12)]
+        );
+        assert_eq!(io.inputs(), &[3, 4]);
+        assert_eq!(io.outputs(), &[12]);
+        assert_eq!(io.inner().outputs(), &[12]);
+    }
+
+    #[test]
+    fn test_chained_io() {
+        let mut io = BasicProgramIO::new(&[4]).chain_inputs(&[3]);
+
+        Program::new("", &[3, 11, 3, 12, 2, 11, 12, 13, 4, 13, 99, 0, 0, 0])
+            .run(&mut io)
+            .unwrap();
+
+        assert_eq!(io.inner().outputs(), &[12]);
+    }
+
+    #[test]
+    fn test_nested_adapters() {
+        let mut basic = BasicProgramIO::new(&[4]);
+        let mut io = (&mut basic).chain_inputs(&[3]).tee().logged("nested");
+
+        Program::new("", &[3, 11, 3, 12, 2, 11, 12, 13, 4, 13, 99, 0, 0, 0])
+            .run(&mut io)
+            .unwrap();
+
+        let tee = io.into_inner();
+        assert_eq!(tee.inputs(), &[3, 4]);
+        assert_eq!(tee.outputs(), &[12]);
+        assert_eq!(basic.outputs(), &[12]);
+    }
+
+    #[test]
+    fn test_channel_io() {
+        let (sender, receiver) = channel();
+        let (mut io, outputs) = ChannelIO::new("", &[3], receiver);
+
+        sender.send(ChannelEvent::Output(4)).unwrap();
+
+        let mut program = Program::new("", &[3, 11, 3, 12, 2, 11, 12, 13, 4, 13, 99, 0, 0, 0]);
+        assert_eq!(io.run(&mut program), Ok(Some(12)));
+        assert_eq!(io.last_output(), Some(12));
+
+        assert_eq!(
+            outputs.try_iter().collect::<Vec<_>>(),
+            vec![ChannelEvent::Output(12), ChannelEvent::Halted]
+        );
+    }
+
+    #[test]
+    fn test_channel_io_adapters() {
+        let (sender, receiver) = channel();
+        let (io, outputs) = ChannelIO::new("", &[], receiver);
+        let mut io = io.chain_inputs(&[3]).tee().logged("channel");
+
+        sender.send(ChannelEvent::Output(4)).unwrap();
+
+        Program::new("", &[3, 11, 3, 12, 2, 11, 12, 13, 4, 13, 99, 0, 0, 0])
+            .run(&mut io)
+            .unwrap();
+
+        let tee = io.into_inner();
+        assert_eq!(tee.inputs(), &[3, 4]);
+        assert_eq!(tee.outputs(), &[12]);
+        assert_eq!(tee.inner().inner().last_output(), Some(12));
+
+        drop(tee);
+        assert_eq!(
+            outputs.iter().collect::<Vec<_>>(),
+            vec![ChannelEvent::Output(12)]
+        );
+    }
+
+    #[test]
+    fn test_channel_io_adapters_closed() {
+        let (sender, receiver) = channel();
+        let (io, outputs) = ChannelIO::new("", &[], receiver);
+        let mut io = io.logged("closed");
+
+        sender.send(ChannelEvent::Halted).unwrap();
+
+        let mut program = Program::new("", &[104, 1, 3, 0, 99]);
+        assert_eq!(
+            program.run(&mut io),
+            Err(IntcodeError::ChannelClosed { address: 2 })
+        );
+        // The failed input isn't written
+        assert_eq!(program.read(0), 104);
+
+        drop(outputs);
+        let mut program = Program::new("", &[104, 1, 99]);
+        assert_eq!(
+            program.run(&mut io),
+            Err(IntcodeError::OutputDisconnected { address: 0 })
+        );
+    }
+
+    #[test]
+    fn test_channel_io_closed() {
+        let (sender, receiver) = channel();
+        let (mut io, outputs) = ChannelIO::new("", &[], receiver);
+
+        sender.send(ChannelEvent::Halted).unwrap();
+
+        let mut program = Program::new("", &[104, 1, 3, 0, 99]);
+        assert_eq!(
+            io.run(&mut program),
+            Err(IntcodeError::ChannelClosed { address: 2 })
+        );
+        assert_eq!(io.last_output(), Some(1));
+
+        assert_eq!(
+            outputs.try_iter().collect::<Vec<_>>(),
+            vec![ChannelEvent::Output(1), ChannelEvent::Halted]
+        );
+    }
 }
@@ -0,0 +1,154 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::error::IntcodeError;
+use super::instruction::Instruction;
+use super::opcode::OpCode;
+use super::parameters::ParameterMode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+/// The instruction set a program was written against
+///
+/// Each level includes everything from the levels below it.
+pub enum InstructionSet {
+    /// Day 2: add, multiply and halt with position mode parameters
+    Basic,
+    /// Day 5: adds input, output, jumps, compares and immediate mode
+    Diagnostic,
+    /// Day 9: adds the relative base and relative mode
+    #[default]
+    Full,
+}
+
+impl InstructionSet {
+    /// The lowest instruction set that includes `operation`
+    pub fn for_opcode(operation: OpCode) -> InstructionSet {
+        match operation {
+            OpCode::Add | OpCode::Multiply | OpCode::Halt => InstructionSet::Basic,
+            OpCode::Input
+            | OpCode::Output
+            | OpCode::JumpIfTrue
+            | OpCode::JumpIfFalse
+            | OpCode::LessThan
+            | OpCode::Equals => InstructionSet::Diagnostic,
+            OpCode::AdjustRelative => InstructionSet::Full,
+        }
+    }
+
+    /// The lowest instruction set that includes `mode`
+    pub fn for_mode(mode: ParameterMode) -> InstructionSet {
+        match mode {
+            ParameterMode::Address => InstructionSet::Basic,
+            ParameterMode::Immediate => InstructionSet::Diagnostic,
+            ParameterMode::Relative => InstructionSet::Full,
+        }
+    }
+
+    /// Check that `instruction` at `address` only uses features from this set
+    pub fn check(self, address: i64, instruction: &Instruction) -> Result<(), IntcodeError> {
+        let operation = instruction.operation();
+        if Self::for_opcode(operation) > self {
+            return Err(IntcodeError::UnsupportedOpCode {
+                address,
+                operation,
+                instruction_set: self,
+            });
+        }
+
+        for mode in instruction.modes() {
+            if Self::for_mode(mode) > self {
+                return Err(IntcodeError::UnsupportedParameterMode {
+                    address,
+                    mode,
+                    instruction_set: self,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for InstructionSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InstructionSet::Basic => write!(f, "Basic (day 2)"),
+            InstructionSet::Diagnostic => write!(f, "Diagnostic (day 5)"),
+            InstructionSet::Full => write!(f, "Full (day 9)"),
+        }
+    }
+}
+
+impl FromStr for InstructionSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "basic" | "day02" => Ok(InstructionSet::Basic),
+            "diagnostic" | "day05" => Ok(InstructionSet::Diagnostic),
+            "full" | "day09" => Ok(InstructionSet::Full),
+            _ => Err(format!("Unknown instruction set: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::io::BasicProgramIO;
+    use crate::Program;
+
+    fn run(code: &[i64], instruction_set: InstructionSet) -> Result<(), IntcodeError> {
+        let mut program = Program::new("", code);
+        program.set_instruction_set(instruction_set);
+        program.expand();
+        program.run(&mut BasicProgramIO::new(&[1]))
+    }
+
+    #[test]
+    fn test_basic_instruction_set() {
+        assert_eq!(run(&[1, 0, 0, 0, 99], InstructionSet::Basic), Ok(()));
+        assert_eq!(
+            run(&[3, 0, 99], InstructionSet::Basic),
+            Err(IntcodeError::UnsupportedOpCode {
+                address: 0,
+                operation: OpCode::Input,
+                instruction_set: InstructionSet::Basic
+            })
+        );
+        assert_eq!(
+            run(&[1, 0, 0, 0, 1101, 1, 1, 0, 99], InstructionSet::Basic),
+            Err(IntcodeError::UnsupportedParameterMode {
+                address: 4,
+                mode: ParameterMode::Immediate,
+                instruction_set: InstructionSet::Basic
+            })
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_instruction_set() {
+        assert_eq!(run(&[3, 0, 4, 0, 99], InstructionSet::Diagnostic), Ok(()));
+        assert_eq!(
+            run(&[109, 1, 99], InstructionSet::Diagnostic),
+            Err(IntcodeError::UnsupportedOpCode {
+                address: 0,
+                operation: OpCode::AdjustRelative,
+                instruction_set: InstructionSet::Diagnostic
+            })
+        );
+        assert_eq!(
+            run(&[204, 0, 99], InstructionSet::Diagnostic),
+            Err(IntcodeError::UnsupportedParameterMode {
+                address: 0,
+                mode: ParameterMode::Relative,
+                instruction_set: InstructionSet::Diagnostic
+            })
+        );
+    }
+
+    #[test]
+    fn test_full_instruction_set() {
+        assert_eq!(run(&[109, 1, 204, 0, 99], InstructionSet::Full), Ok(()));
+    }
+}
@@ -0,0 +1,98 @@
+use super::error::IntcodeError;
+use super::{Program, Step};
+
+/// Iterator over the outputs of a running program
+///
+/// Created by `Program::outputs`
+pub struct Outputs<'a, I: Iterator<Item = i64>> {
+    program: &'a mut Program,
+    inputs: I,
+    finished: bool,
+}
+
+impl<'a, I: Iterator<Item = i64>> Outputs<'a, I> {
+    pub(super) fn new(program: &'a mut Program, inputs: I) -> Outputs<'a, I> {
+        Outputs {
+            program,
+            inputs,
+            finished: false,
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = i64>> Iterator for Outputs<'a, I> {
+    type Item = Result<i64, IntcodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let inputs = &mut self.inputs;
+
+        loop {
+            match self.program.step_with(|| inputs.next()) {
+                Ok(Step::Continue) => {}
+                Ok(Step::Output(output)) => return Some(Ok(output)),
+                Ok(Step::NeedsInput) => {
+                    self.finished = true;
+                    return Some(Err(IntcodeError::InputExhausted {
+                        address: self.program.current_address(),
+                    }));
+                }
+                Ok(Step::Halted) => {
+                    self.finished = true;
+                    return None;
+                }
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use super::*;
+
+    #[test]
+    fn test_outputs_last() {
+        let mut program = Program::new("", &[3, 0, 4, 0, 4, 0, 99]);
+
+        assert_eq!(program.outputs(vec![14]).last(), Some(Ok(14)));
+    }
+
+    #[test]
+    fn test_outputs_lazy() {
+        // Outputs once, then asks for input it will never get
+        let mut program = Program::new("", &[104, 7, 3, 0, 99]);
+        let mut outputs = program.outputs(vec![]);
+
+        assert_eq!(outputs.next(), Some(Ok(7)));
+        assert_eq!(
+            outputs.next(),
+            Some(Err(IntcodeError::InputExhausted { address: 2 }))
+        );
+        assert_eq!(outputs.next(), None);
+
+        // The program is paused on the input and can be resumed
+        assert_eq!(program.outputs(vec![1]).collect::<Vec<_>>(), vec![]);
+    }
+
+    #[test]
+    fn test_outputs_tuples() {
+        let mut program = Program::new("", &[104, 1, 104, 2, 104, 3, 104, 4, 99]);
+
+        let pairs: Vec<(i64, i64)> = program
+            .outputs(vec![])
+            .map(Result::unwrap)
+            .tuples()
+            .collect();
+
+        assert_eq!(pairs, vec![(1, 2), (3, 4)]);
+    }
+}
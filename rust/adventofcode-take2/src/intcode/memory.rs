@@ -1,10 +1,31 @@
+use std::hash::{Hash, Hasher};
 use std::ops::{Index, IndexMut};
+use std::sync::{Arc, OnceLock};
 
+use super::error::IntcodeError;
 use super::parameters::{Parameter, ParameterMode};
 
+/// Number of cells in each page of memory
+const PAGE_SIZE: usize = 1024;
+
+type Page = [i64; PAGE_SIZE];
+
+/// The page shared by all freshly expanded memory
+fn zero_page() -> Arc<Page> {
+    static ZERO: OnceLock<Arc<Page>> = OnceLock::new();
+    ZERO.get_or_init(|| Arc::new([0; PAGE_SIZE])).clone()
+}
+
 #[derive(Debug, Clone)]
+/// Memory split into pages that are shared between clones
+///
+/// Cloning only copies the page table. A page is copied the first time a
+/// clone writes to it, so forking a program costs the pages it touches
+/// rather than its whole memory.
 pub struct ProgramMemory {
-    memory: Vec<i64>,
+    pages: Vec<Arc<Page>>,
+    len: usize,
+    image_size: i64,
     relative_base: i64,
     current_address: i64,
 }
@@ -12,7 +33,9 @@ pub struct ProgramMemory {
 impl ProgramMemory {
     pub fn new() -> ProgramMemory {
         ProgramMemory {
-            memory: Vec::new(),
+            pages: Vec::new(),
+            len: 0,
+            image_size: 0,
             relative_base: 0,
             current_address: 0,
         }
@@ -20,12 +43,26 @@ impl ProgramMemory {
 
     pub fn from_buffer(buffer: &[i64]) -> ProgramMemory {
         ProgramMemory {
-            memory: buffer.iter().copied().collect(),
+            pages: buffer
+                .chunks(PAGE_SIZE)
+                .map(|chunk| {
+                    let mut page = [0; PAGE_SIZE];
+                    page[..chunk.len()].copy_from_slice(chunk);
+                    Arc::new(page)
+                })
+                .collect(),
+            len: buffer.len(),
+            image_size: buffer.len() as i64,
             relative_base: 0,
             current_address: 0,
         }
     }
 
+    /// Number of cells loaded from the program, before any expansion
+    pub fn image_size(&self) -> i64 {
+        self.image_size
+    }
+
     pub fn current_address(&self) -> i64 {
         self.current_address
     }
@@ -38,38 +75,124 @@ impl ProgramMemory {
         self.current_address += value;
     }
 
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
     pub fn adjust_relative(&mut self, value: i64) {
         self.relative_base += value;
     }
 
-    pub fn value(&self, param: impl ParameterArgument) -> i64 {
-        match param.get_parameter_argument_mode() {
-            ParameterMode::Immediate => param.get_parameter_argument_value(),
-            ParameterMode::Address => self[param.get_parameter_argument_value()],
-            ParameterMode::Relative => {
-                self[self.relative_base + param.get_parameter_argument_value()]
+    pub fn value(&self, param: impl ParameterArgument) -> Result<i64, IntcodeError> {
+        let address = match param.get_parameter_argument_mode() {
+            ParameterMode::Immediate => return Ok(param.get_parameter_argument_value()),
+            ParameterMode::Address => param.get_parameter_argument_value(),
+            ParameterMode::Relative => self.relative_base + param.get_parameter_argument_value(),
+        };
+
+        self.get(address)
+            .ok_or(IntcodeError::AddressOutOfRange { address })
+    }
+
+    pub fn address_mut(&mut self, param: impl ParameterArgument) -> Result<&mut i64, IntcodeError> {
+        let address = match param.get_parameter_argument_mode() {
+            ParameterMode::Immediate => {
+                return Err(IntcodeError::ImmediateDestination {
+                    address: self.current_address,
+                })
             }
+            ParameterMode::Address => param.get_parameter_argument_value(),
+            ParameterMode::Relative => self.relative_base + param.get_parameter_argument_value(),
+        };
+
+        if address < 0 || address as usize >= self.len {
+            return Err(IntcodeError::AddressOutOfRange { address });
         }
+        Ok(&mut self[address])
     }
 
-    pub fn address_mut(&mut self, param: impl ParameterArgument) -> &mut i64 {
-        match param.get_parameter_argument_mode() {
-            ParameterMode::Immediate => panic!("ref_mut on immediate value"),
-            ParameterMode::Address => &mut self[param.get_parameter_argument_value()],
-            ParameterMode::Relative => {
-                let address = self.relative_base + param.get_parameter_argument_value();
-                &mut self[address]
+    /// The address `param` refers to, or None for an immediate value
+    pub fn resolve(&self, param: Parameter) -> Option<i64> {
+        match param.mode() {
+            ParameterMode::Immediate => None,
+            ParameterMode::Address => Some(param.value()),
+            ParameterMode::Relative => Some(self.relative_base + param.value()),
+        }
+    }
+
+    /// The value at `address`, or None if it is outside memory
+    pub fn get(&self, address: i64) -> Option<i64> {
+        if address < 0 || address as usize >= self.len {
+            return None;
+        }
+        Some(self[address])
+    }
+
+    /// Number of cells in memory
+    pub fn len(&self) -> i64 {
+        self.len as i64
+    }
+
+    /// Grow or shrink memory to `size` cells, filling new cells with zeros
+    pub fn resize(&mut self, size: usize) {
+        if size < self.len {
+            // Cells past the end must be zero in case memory grows again
+            let offset = size % PAGE_SIZE;
+            self.pages.truncate(size.div_ceil(PAGE_SIZE));
+            if offset != 0 {
+                if let Some(page) = self.pages.last_mut() {
+                    Arc::make_mut(page)[offset..]
+                        .iter_mut()
+                        .for_each(|v| *v = 0);
+                }
             }
         }
+
+        self.pages.resize(size.div_ceil(PAGE_SIZE), zero_page());
+        self.len = size;
     }
 
-    pub fn expand(&mut self, size: i64) {
-        self.memory.resize(size as usize, 0);
+    /// Copy all of memory into one buffer
+    pub fn dump(&self) -> Vec<i64> {
+        self.pages
+            .iter()
+            .flat_map(|page| page.iter().copied())
+            .take(self.len)
+            .collect()
     }
 
-    #[cfg(test)]
-    pub fn dump(&self) -> &[i64] {
-        &self.memory
+    fn locate(&self, address: i64) -> (usize, usize) {
+        if address < 0 || address as usize >= self.len {
+            panic!(
+                "Address {} is outside of memory of size {}",
+                address, self.len
+            );
+        }
+
+        let address = address as usize;
+        (address / PAGE_SIZE, address % PAGE_SIZE)
+    }
+}
+
+impl Hash for ProgramMemory {
+    /// Hashes the cells, relative base and current address
+    ///
+    /// Pages of zeros are skipped, so a page written back to zero hashes the
+    /// same as one that was never touched.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let zero = zero_page();
+
+        self.len.hash(state);
+        self.relative_base.hash(state);
+        self.current_address.hash(state);
+
+        for (index, page) in self.pages.iter().enumerate() {
+            if Arc::ptr_eq(page, &zero) || page.iter().all(|v| *v == 0) {
+                continue;
+            }
+            index.hash(state);
+            page.hash(state);
+        }
     }
 }
 
@@ -77,13 +200,15 @@ impl Index<i64> for ProgramMemory {
     type Output = i64;
 
     fn index(&self, idx: i64) -> &Self::Output {
-        self.memory.index(idx as usize)
+        let (page, offset) = self.locate(idx);
+        &self.pages[page][offset]
     }
 }
 
 impl IndexMut<i64> for ProgramMemory {
     fn index_mut(&mut self, idx: i64) -> &mut Self::Output {
-        self.memory.index_mut(idx as usize)
+        let (page, offset) = self.locate(idx);
+        &mut Arc::make_mut(&mut self.pages[page])[offset]
     }
 }
 
@@ -116,13 +241,73 @@ mod test {
     #[test]
     fn test_memory_create() {
         let mem = ProgramMemory::new();
-        assert_eq!(mem.memory, &[]);
+        assert!(mem.dump().is_empty());
 
         let mem = ProgramMemory::from_buffer(&[1, 2, 3, 4]);
-        assert_eq!(mem.memory, &[1, 2, 3, 4]);
+        assert_eq!(mem.dump(), &[1, 2, 3, 4]);
 
         let v = vec![1, 2, 3, 4];
         let mem = ProgramMemory::from_buffer(&v);
-        assert_eq!(mem.memory, &[1, 2, 3, 4]);
+        assert_eq!(mem.dump(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_memory_resize() {
+        let mut mem = ProgramMemory::from_buffer(&[1, 2, 3]);
+
+        mem.resize(PAGE_SIZE * 2 + 1);
+        assert_eq!(mem.len(), PAGE_SIZE as i64 * 2 + 1);
+        assert_eq!(mem[2], 3);
+        assert_eq!(mem[PAGE_SIZE as i64 * 2], 0);
+        assert_eq!(mem.get(PAGE_SIZE as i64 * 2 + 1), None);
+
+        mem[PAGE_SIZE as i64 * 2] = 9;
+        mem.resize(2);
+        assert_eq!(mem.dump(), &[1, 2]);
+
+        mem.resize(4);
+        assert_eq!(mem.dump(), &[1, 2, 0, 0]);
+    }
+
+    #[test]
+    fn test_memory_hash() {
+        let hash = |memory: &ProgramMemory| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            memory.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let mut mem = ProgramMemory::from_buffer(&[1, 2, 3]);
+        mem.resize(PAGE_SIZE * 2);
+        let mut fork = mem.clone();
+
+        fork[PAGE_SIZE as i64] = 5;
+        assert_ne!(hash(&mem), hash(&fork));
+
+        fork[PAGE_SIZE as i64] = 0;
+        assert_eq!(hash(&mem), hash(&fork));
+
+        fork.advance(1);
+        assert_ne!(hash(&mem), hash(&fork));
+    }
+
+    #[test]
+    fn test_memory_copy_on_write() {
+        let mut mem = ProgramMemory::from_buffer(&[1, 2, 3]);
+        mem.resize(PAGE_SIZE * 4);
+
+        let mut fork = mem.clone();
+        fork[PAGE_SIZE as i64 * 2] = 7;
+
+        assert_eq!(mem[PAGE_SIZE as i64 * 2], 0);
+        assert_eq!(fork[PAGE_SIZE as i64 * 2], 7);
+
+        let shared = mem
+            .pages
+            .iter()
+            .zip(&fork.pages)
+            .filter(|(a, b)| Arc::ptr_eq(a, b))
+            .count();
+        assert_eq!(shared, 3);
     }
 }
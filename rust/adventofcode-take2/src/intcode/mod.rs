@@ -1,21 +1,69 @@
+pub mod debug;
+pub mod error;
+pub mod ffi;
+pub mod framed;
+pub mod inspect;
 mod instruction;
 pub mod io;
+pub mod isa;
+mod iter;
 mod memory;
 mod opcode;
 mod parameters;
+pub mod sanitizer;
+pub mod scheduler;
+pub mod search;
+pub mod session;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 use tracing::info;
 
+use debug::Debugger;
+use error::IntcodeError;
 use instruction::Instruction;
 use io::ProgramIO;
+use isa::InstructionSet;
+use iter::Outputs;
 use memory::ProgramMemory;
-use opcode::ExecutionState;
+use opcode::{ExecutionState, OpCode};
+use sanitizer::{Sanitizer, SanitizerReport};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The outcome of a single `Program::step_with`
+pub enum Step {
+    Continue,
+    Output(i64),
+    NeedsInput,
+    Halted,
+}
 
 #[derive(Debug, Clone)]
 /// An intcode program
 pub struct Program {
     name: String,
     memory: ProgramMemory,
+    instruction_set: InstructionSet,
+    sanitizer: Option<Sanitizer>,
+    debugger: Attachment,
+}
+
+#[derive(Debug, Default)]
+/// A debugger attached to a single program
+///
+/// Clones start detached, since only one program can answer a client.
+struct Attachment {
+    debugger: Option<Box<Debugger>>,
+    /// The debugger has already seen the next instruction, which hasn't run
+    /// yet because it was waiting for input
+    consulted: bool,
+}
+
+impl Clone for Attachment {
+    fn clone(&self) -> Self {
+        Attachment::default()
+    }
 }
 
 const PROGRAM_SIZE: i64 = 1024 * 1024 * 4;
@@ -25,49 +73,213 @@ impl Program {
         Program {
             name: name.to_string(),
             memory: ProgramMemory::from_buffer(memory),
+            instruction_set: InstructionSet::default(),
+            sanitizer: None,
+            debugger: Attachment::default(),
         }
     }
 
+    /// Grow memory to the standard size, if it isn't already bigger
     pub fn expand(&mut self) {
-        self.memory.expand(PROGRAM_SIZE);
+        if self.memory.len() < PROGRAM_SIZE {
+            self.memory.resize(PROGRAM_SIZE as usize);
+        }
+    }
+
+    /// Grow memory to exactly `size` cells, which can't be fewer than it has
+    pub fn expand_to(&mut self, size: i64) -> Result<(), IntcodeError> {
+        if size < self.memory.len() {
+            return Err(IntcodeError::MemoryTooSmall {
+                size,
+                required: self.memory.len(),
+            });
+        }
+
+        self.memory.resize(size as usize);
+        Ok(())
+    }
+
+    /// Restrict the program to an older instruction set
+    pub fn set_instruction_set(&mut self, instruction_set: InstructionSet) {
+        self.instruction_set = instruction_set;
     }
 
-    pub fn from_str(name: &str, code: &str) -> Program {
-        Self::new(
-            name,
-            &code
-                .split(',')
-                .map(|s| s.trim().parse::<i64>().unwrap())
-                .collect::<Vec<_>>(),
-        )
+    /// Check every instruction from now on for suspicious memory use
+    ///
+    /// Only cells from the original program count as initialized, even if
+    /// memory has already been expanded.
+    pub fn enable_sanitizer(&mut self) {
+        self.sanitizer = Some(Sanitizer::new(self.memory.image_size()));
     }
 
-    pub fn run(&mut self, io: &mut impl ProgramIO) {
-        loop {
-            let (instruction, size) = Instruction::new(self.memory.current_address(), &self.memory);
+    /// Let a remote client control this program before each instruction
+    ///
+    /// The program is paused until a client connects and continues it.
+    pub fn attach_debugger(&mut self, debugger: Debugger) {
+        self.debugger = Attachment {
+            debugger: Some(Box::new(debugger)),
+            consulted: false,
+        };
+    }
 
-            info!(
-                "{}: Instruction(#{}): {}",
-                self.name,
+    /// Everything the sanitizer has noticed so far
+    pub fn sanitizer_reports(&self) -> &[SanitizerReport] {
+        self.sanitizer.as_ref().map_or(&[], |s| s.reports())
+    }
+
+    pub fn read(&self, address: i64) -> i64 {
+        self.memory[address]
+    }
+
+    pub fn write(&mut self, address: i64, value: i64) {
+        if let Some(sanitizer) = &mut self.sanitizer {
+            sanitizer.record_write(address);
+        }
+        self.memory[address] = value;
+    }
+
+    /// A copy of the full contents of memory
+    pub fn dump(&self) -> Vec<i64> {
+        self.memory.dump()
+    }
+
+    /// Number of cells in memory
+    pub fn memory_size(&self) -> i64 {
+        self.memory.len()
+    }
+
+    pub fn run(&mut self, io: &mut impl ProgramIO) -> Result<(), IntcodeError> {
+        while self.step(io)? {}
+        Ok(())
+    }
+
+    /// Execute a single instruction, returning false once the program halts
+    pub fn step(&mut self, io: &mut impl ProgramIO) -> Result<bool, IntcodeError> {
+        self.consult_debugger();
+        self.debugger.consulted = false;
+
+        let (instruction, size) = self.next_instruction()?;
+
+        if let Some(sanitizer) = &mut self.sanitizer {
+            sanitizer.check(
                 self.memory.current_address(),
-                instruction
+                &instruction,
+                size,
+                &self.memory,
             );
+        }
+
+        info!(
+            "{}: Instruction(#{}): {}",
+            self.name,
+            self.memory.current_address(),
+            instruction
+        );
+
+        match instruction.execute(&mut self.memory, io)? {
+            ExecutionState::Halt => return Ok(false),
+            ExecutionState::Continue => {
+                self.memory.advance(size);
+            }
+            ExecutionState::Jump(n) => {
+                self.memory.jump(n);
+            }
+            ExecutionState::AdjustRelative(n) => {
+                self.memory.adjust_relative(n);
+                self.memory.advance(size);
+            }
+        };
+
+        Ok(true)
+    }
 
-            match instruction.execute(&mut self.memory, io) {
-                ExecutionState::Halt => break,
-                ExecutionState::Continue => {
-                    self.memory.advance(size);
-                }
-                ExecutionState::Jump(n) => {
-                    self.memory.jump(n);
-                }
-                ExecutionState::AdjustRelative(n) => {
-                    self.memory.adjust_relative(n);
-                    self.memory.advance(size);
-                }
-            };
+    /// Execute a single instruction, reporting what it did
+    ///
+    /// `input` is only called when the instruction is an input. If it returns
+    /// `None` the instruction is not executed and the program stays paused on
+    /// it, so it can be stepped again once input is available.
+    pub fn step_with(&mut self, input: impl FnOnce() -> Option<i64>) -> Result<Step, IntcodeError> {
+        let mut io = StepIO::default();
+
+        // The debugger can rewrite the instruction, so it goes before decoding
+        self.consult_debugger();
+
+        if self.next_instruction()?.0.operation() == OpCode::Input {
+            match input() {
+                Some(value) => io.input = Some(value),
+                None => return Ok(Step::NeedsInput),
+            }
+        }
+
+        if !self.step(&mut io)? {
+            return Ok(Step::Halted);
+        }
+
+        Ok(match io.output {
+            Some(output) => Step::Output(output),
+            None => Step::Continue,
+        })
+    }
+
+    /// Hash of memory, the current address and the relative base
+    ///
+    /// Used to spot when two runs have reached the same state.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.memory.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Address of the next instruction to execute
+    pub fn current_address(&self) -> i64 {
+        self.memory.current_address()
+    }
+
+    /// Run the program lazily, yielding each output as it is produced
+    ///
+    /// The program only runs until its next output. If it asks for input after
+    /// `inputs` is exhausted an error is yielded and the program is left paused
+    /// on the input instruction.
+    pub fn outputs<I>(&mut self, inputs: I) -> Outputs<'_, I::IntoIter>
+    where
+        I: IntoIterator<Item = i64>,
+    {
+        Outputs::new(self, inputs.into_iter())
+    }
+
+    /// Let the debugger see the next instruction, once however often it is tried
+    fn consult_debugger(&mut self) {
+        if let (Some(debugger), false) = (&mut self.debugger.debugger, self.debugger.consulted) {
+            debugger.before_step(&mut self.memory, self.sanitizer.as_mut());
+            self.debugger.consulted = true;
         }
     }
+
+    fn next_instruction(&self) -> Result<(Instruction, i64), IntcodeError> {
+        let address = self.memory.current_address();
+        let (instruction, size) = Instruction::new(address, &self.memory)?;
+
+        self.instruction_set.check(address, &instruction)?;
+
+        Ok((instruction, size))
+    }
+}
+
+#[derive(Default)]
+/// Holds the IO for a single instruction
+struct StepIO {
+    input: Option<i64>,
+    output: Option<i64>,
+}
+
+impl ProgramIO for StepIO {
+    fn get_next_input(&mut self) -> i64 {
+        self.input.take().expect("Input was not staged")
+    }
+
+    fn receive_output(&mut self, output: i64) {
+        self.output = Some(output);
+    }
 }
 
 impl Default for Program {
@@ -75,6 +287,9 @@ impl Default for Program {
         Self {
             name: "".to_string(),
             memory: ProgramMemory::new(),
+            instruction_set: InstructionSet::default(),
+            sanitizer: None,
+            debugger: Attachment::default(),
         }
     }
 }
@@ -85,13 +300,13 @@ mod test {
     use super::*;
 
     fn init_logging() {
-        let _ = env_logger::builder().is_test(true).try_init();
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
     }
 
     fn run_program(name: &str, code: &[i64], inputs: &[i64], expected_output: &[i64]) {
         let mut io = BasicProgramIO::new(inputs);
         let mut program = Program::new("", code);
-        program.run(&mut io);
+        program.run(&mut io).unwrap();
         assert_eq!(io.outputs(), expected_output, "{}", name);
     }
 
@@ -100,8 +315,8 @@ mod test {
         let test_fn = |actual: &mut [i64], expected: &[i64]| {
             let mut io = BasicProgramIO::new(&[]);
             let mut program = Program::new("", actual);
-            program.run(&mut io);
-            assert_eq!(program.memory.dump(), expected);
+            program.run(&mut io).unwrap();
+            assert_eq!(program.dump(), expected);
         };
 
         test_fn(&mut [1, 0, 0, 0, 99], &[2, 0, 0, 0, 99]);
@@ -116,10 +331,10 @@ mod test {
     #[test]
     fn test_intcode_io() {
         let mut io = BasicProgramIO::new(&[14]);
-        let mut program = Program::new("", &vec![3, 0, 4, 0, 99]);
-        program.run(&mut io);
+        let mut program = Program::new("", &[3, 0, 4, 0, 99]);
+        program.run(&mut io).unwrap();
 
-        assert_eq!(program.memory.dump(), &[14, 0, 4, 0, 99]);
+        assert_eq!(program.dump(), &[14, 0, 4, 0, 99]);
         assert_eq!(io.outputs(), &[14]);
     }
 
@@ -160,13 +375,13 @@ mod test {
         // eq - position mode
         run_program(
             "eq pos mode - true",
-            &vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
             &[8],
             &[1],
         );
         run_program(
             "eq pos mode - false",
-            &vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8],
             &[7],
             &[0],
         );
@@ -174,19 +389,19 @@ mod test {
         // lt - position mode
         run_program(
             "lt pos mode - true",
-            &vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
             &[7],
             &[1],
         );
         run_program(
             "lt pos mode - false 1",
-            &vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
             &[8],
             &[0],
         );
         run_program(
             "lt pos mode - false 2",
-            &vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
+            &[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8],
             &[9],
             &[0],
         );
@@ -194,13 +409,13 @@ mod test {
         // eq - immediate mode
         run_program(
             "eq immediate mode - true",
-            &vec![3, 3, 1108, -1, 8, 3, 4, 3, 99],
+            &[3, 3, 1108, -1, 8, 3, 4, 3, 99],
             &[8],
             &[1],
         );
         run_program(
             "eq immediate mode - false",
-            &vec![3, 3, 1108, -1, 8, 3, 4, 3, 99],
+            &[3, 3, 1108, -1, 8, 3, 4, 3, 99],
             &[7],
             &[0],
         );
@@ -208,19 +423,19 @@ mod test {
         // lt - immediate mode
         run_program(
             "lt immediate mode - true",
-            &vec![3, 3, 1107, -1, 8, 3, 4, 3, 99],
+            &[3, 3, 1107, -1, 8, 3, 4, 3, 99],
             &[7],
             &[1],
         );
         run_program(
             "lt immediate mode - false 1",
-            &vec![3, 3, 1107, -1, 8, 3, 4, 3, 99],
+            &[3, 3, 1107, -1, 8, 3, 4, 3, 99],
             &[8],
             &[0],
         );
         run_program(
             "lt immediate mode - false 2",
-            &vec![3, 3, 1107, -1, 8, 3, 4, 3, 99],
+            &[3, 3, 1107, -1, 8, 3, 4, 3, 99],
             &[9],
             &[0],
         );
@@ -273,4 +488,42 @@ mod test {
             &[1001],
         );
     }
+
+    #[test]
+    fn test_expand_to() {
+        let mut program = Program::new("", &[1, 2, 3]);
+
+        assert_eq!(program.expand_to(5), Ok(()));
+        assert_eq!(program.dump(), &[1, 2, 3, 0, 0]);
+        assert_eq!(
+            program.expand_to(2),
+            Err(IntcodeError::MemoryTooSmall {
+                size: 2,
+                required: 5
+            })
+        );
+        assert!(program.expand_to(-5).is_err());
+        assert_eq!(program.memory_size(), 5);
+    }
+
+    #[test]
+    fn test_bad_addresses() {
+        let run = |code: &[i64]| {
+            let mut io = BasicProgramIO::new(&[]);
+            Program::new("", code).run(&mut io)
+        };
+
+        assert_eq!(
+            run(&[1101, 1, 1, 100, 99]),
+            Err(IntcodeError::AddressOutOfRange { address: 100 })
+        );
+        assert_eq!(
+            run(&[109, -10, 22101, 1, 0, 0, 99]),
+            Err(IntcodeError::AddressOutOfRange { address: -10 })
+        );
+        assert_eq!(
+            run(&[11101, 1, 1, 0, 99]),
+            Err(IntcodeError::ImmediateDestination { address: 0 })
+        );
+    }
 }
@@ -6,7 +6,7 @@ pub enum ExecutionState {
     AdjustRelative(i64),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OpCode {
     Add,
     Multiply,
@@ -1,6 +1,6 @@
 use std::fmt;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ParameterMode {
     Immediate,
     Address,
@@ -8,18 +8,13 @@ pub enum ParameterMode {
 }
 
 impl ParameterMode {
-    pub fn from_opcode(opcode: i64, index: i64) -> ParameterMode {
-        (opcode / 10i64.pow((2 + index) as u32) % 10).into()
-    }
-}
-
-impl From<i64> for ParameterMode {
-    fn from(x: i64) -> ParameterMode {
-        match x {
-            0 => ParameterMode::Address,
-            1 => ParameterMode::Immediate,
-            2 => ParameterMode::Relative,
-            _ => panic!("Unknown parameter mode"),
+    /// The mode of parameter `index`, or None if the opcode has an unknown mode
+    pub fn from_opcode(opcode: i64, index: i64) -> Option<ParameterMode> {
+        match opcode / 10i64.pow((2 + index) as u32) % 10 {
+            0 => Some(ParameterMode::Address),
+            1 => Some(ParameterMode::Immediate),
+            2 => Some(ParameterMode::Relative),
+            _ => None,
         }
     }
 }
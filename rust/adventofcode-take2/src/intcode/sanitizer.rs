@@ -0,0 +1,195 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::instruction::Instruction;
+use super::memory::ProgramMemory;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Suspicious memory use noticed by a `Sanitizer`
+///
+/// `address` is always the instruction responsible.
+pub enum SanitizerReport {
+    /// Read `cell`, which is past the original image and was never written
+    UninitializedRead { address: i64, cell: i64 },
+    /// Wrote `cell`, which was already run as part of `instruction`
+    SelfModification {
+        address: i64,
+        cell: i64,
+        instruction: i64,
+    },
+    /// Jumped to `target`, which is in the middle of `instruction`
+    MisalignedJump {
+        address: i64,
+        target: i64,
+        instruction: i64,
+    },
+}
+
+impl fmt::Display for SanitizerReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanitizerReport::UninitializedRead { address, cell } => {
+                write!(f, "#{}: read of uninitialized #{}", address, cell)
+            }
+            SanitizerReport::SelfModification {
+                address,
+                cell,
+                instruction,
+            } => write!(
+                f,
+                "#{}: write to #{}, part of the instruction at #{}",
+                address, cell, instruction
+            ),
+            SanitizerReport::MisalignedJump {
+                address,
+                target,
+                instruction,
+            } => write!(
+                f,
+                "#{}: jump to #{}, inside the instruction at #{}",
+                address, target, instruction
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Watches a running program for memory use that is usually a bug
+///
+/// Each instruction is checked before it runs. Reports are collected rather
+/// than stopping the program, and each distinct report is only kept once.
+pub struct Sanitizer {
+    image_size: i64,
+    written: HashSet<i64>,
+    /// Start of the executed instruction covering each cell
+    executed: HashMap<i64, i64>,
+    previous: Option<i64>,
+    reports: Vec<SanitizerReport>,
+}
+
+impl Sanitizer {
+    /// Treat the first `image_size` cells as initialized
+    pub fn new(image_size: i64) -> Sanitizer {
+        Sanitizer {
+            image_size,
+            written: HashSet::new(),
+            executed: HashMap::new(),
+            previous: None,
+            reports: Vec::new(),
+        }
+    }
+
+    pub fn reports(&self) -> &[SanitizerReport] {
+        &self.reports
+    }
+
+    /// Note a write made from outside the program
+    pub fn record_write(&mut self, cell: i64) {
+        self.written.insert(cell);
+    }
+
+    /// Check `instruction`, about to run at `address`
+    pub fn check(
+        &mut self,
+        address: i64,
+        instruction: &Instruction,
+        size: i64,
+        memory: &ProgramMemory,
+    ) {
+        if let Some(&start) = self.executed.get(&address) {
+            if start != address {
+                self.report(SanitizerReport::MisalignedJump {
+                    address: self.previous.unwrap_or(address),
+                    target: address,
+                    instruction: start,
+                });
+            }
+        }
+
+        for cell in instruction.sources().filter_map(|p| memory.resolve(p)) {
+            if cell >= self.image_size && !self.written.contains(&cell) {
+                self.report(SanitizerReport::UninitializedRead { address, cell });
+            }
+        }
+
+        if let Some(cell) = instruction.destination().and_then(|p| memory.resolve(p)) {
+            if let Some(&start) = self.executed.get(&cell) {
+                self.report(SanitizerReport::SelfModification {
+                    address,
+                    cell,
+                    instruction: start,
+                });
+            }
+            self.written.insert(cell);
+        }
+
+        for cell in address..address + size {
+            self.executed.insert(cell, address);
+        }
+        self.previous = Some(address);
+    }
+
+    fn report(&mut self, report: SanitizerReport) {
+        if !self.reports.contains(&report) {
+            self.reports.push(report);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::io::BasicProgramIO;
+    use crate::Program;
+
+    fn reports(code: &[i64]) -> Vec<SanitizerReport> {
+        let mut program = Program::new("", code);
+        program.expand_to(64).unwrap();
+        program.enable_sanitizer();
+        program.run(&mut BasicProgramIO::new(&[])).unwrap();
+        program.sanitizer_reports().to_vec()
+    }
+
+    #[test]
+    fn test_clean_program() {
+        // Writes past the image before reading it back
+        assert!(reports(&[1101, 2, 3, 20, 1001, 20, 1, 21, 99]).is_empty());
+    }
+
+    #[test]
+    fn test_uninitialized_read() {
+        assert_eq!(
+            reports(&[1001, 20, 1, 21, 99]),
+            vec![SanitizerReport::UninitializedRead {
+                address: 0,
+                cell: 20
+            }]
+        );
+    }
+
+    #[test]
+    fn test_self_modification() {
+        // The second add patches the first add's opcode into a multiply
+        assert_eq!(
+            reports(&[1101, 1, 1, 20, 1101, 1, 1, 0, 1105, 1, 11, 99]),
+            vec![SanitizerReport::SelfModification {
+                address: 4,
+                cell: 0,
+                instruction: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_misaligned_jump() {
+        // Jumps back into the first add's operand, which holds 99
+        assert_eq!(
+            reports(&[1101, 99, 0, 20, 1105, 1, 1]),
+            vec![SanitizerReport::MisalignedJump {
+                address: 4,
+                target: 1,
+                instruction: 0
+            }]
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::VecDeque;
+
+use tracing::info;
+
+use super::error::IntcodeError;
+use super::{Program, Step};
+
+/// Index of a machine added to a `Scheduler`
+pub type MachineId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Something observable that happened while the scheduler was running
+pub enum Event {
+    Input { machine: MachineId, value: i64 },
+    Output { machine: MachineId, value: i64 },
+    Halted { machine: MachineId },
+}
+
+#[derive(Debug)]
+struct Machine {
+    program: Program,
+    inputs: VecDeque<i64>,
+    outputs: Vec<i64>,
+    targets: Vec<MachineId>,
+    halted: bool,
+}
+
+#[derive(Debug, Default)]
+/// Runs several programs round-robin on a single thread
+///
+/// Each machine runs until it produces an output, halts or needs input that
+/// isn't queued yet, then the next machine gets a turn. Outputs are copied to
+/// the input queues of every connected machine. Because the order is fixed a
+/// run always produces the same `events`.
+pub struct Scheduler {
+    machines: Vec<Machine>,
+    events: Vec<Event>,
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler::default()
+    }
+
+    /// Add a program with some initial inputs already queued
+    pub fn add(&mut self, program: Program, first_inputs: &[i64]) -> MachineId {
+        self.machines.push(Machine {
+            program,
+            inputs: first_inputs.iter().copied().collect(),
+            outputs: Vec::new(),
+            targets: Vec::new(),
+            halted: false,
+        });
+
+        self.machines.len() - 1
+    }
+
+    /// Send all future outputs from `from` to the inputs of `to`
+    pub fn connect(&mut self, from: MachineId, to: MachineId) {
+        self.machines[from].targets.push(to);
+    }
+
+    pub fn push_input(&mut self, machine: MachineId, value: i64) {
+        self.machines[machine].inputs.push_back(value);
+    }
+
+    /// Every output `machine` has produced so far
+    pub fn outputs(&self, machine: MachineId) -> &[i64] {
+        &self.machines[machine].outputs
+    }
+
+    pub fn is_halted(&self, machine: MachineId) -> bool {
+        self.machines[machine].halted
+    }
+
+    /// Every event so far in the order it happened
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Run until every machine has halted
+    pub fn run(&mut self) -> Result<(), IntcodeError> {
+        loop {
+            let mut progressed = false;
+
+            for machine in 0..self.machines.len() {
+                progressed |= self.run_turn(machine)?;
+            }
+
+            let blocked: Vec<_> = (0..self.machines.len())
+                .filter(|m| !self.machines[*m].halted)
+                .collect();
+
+            if blocked.is_empty() {
+                return Ok(());
+            }
+
+            if !progressed {
+                return Err(IntcodeError::Deadlock { blocked });
+            }
+        }
+    }
+
+    /// Give `id` a turn, returning false if it could not do anything
+    fn run_turn(&mut self, id: MachineId) -> Result<bool, IntcodeError> {
+        let mut progressed = false;
+
+        loop {
+            let machine = &mut self.machines[id];
+
+            if machine.halted {
+                return Ok(progressed);
+            }
+
+            let inputs = &mut machine.inputs;
+            let events = &mut self.events;
+
+            let step = machine.program.step_with(|| {
+                let value = inputs.pop_front()?;
+                events.push(Event::Input { machine: id, value });
+                Some(value)
+            })?;
+
+            match step {
+                Step::Continue => progressed = true,
+                Step::Output(value) => {
+                    info!("Machine {} - Output {}", id, value);
+                    self.events.push(Event::Output { machine: id, value });
+                    machine.outputs.push(value);
+
+                    for target in machine.targets.clone() {
+                        self.machines[target].inputs.push_back(value);
+                    }
+
+                    return Ok(true);
+                }
+                Step::NeedsInput => {
+                    info!("Machine {} - Blocked on input", id);
+                    return Ok(progressed);
+                }
+                Step::Halted => {
+                    info!("Machine {} - Halted", id);
+                    machine.halted = true;
+                    self.events.push(Event::Halted { machine: id });
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FEEDBACK: [i64; 29] = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    fn feedback_loop(phase_settings: &[i64]) -> Scheduler {
+        let mut scheduler = Scheduler::new();
+
+        for phase_setting in phase_settings {
+            scheduler.add(Program::new("", &FEEDBACK), &[*phase_setting]);
+        }
+
+        for machine in 0..phase_settings.len() {
+            scheduler.connect(machine, (machine + 1) % phase_settings.len());
+        }
+
+        scheduler.push_input(0, 0);
+        scheduler.run().unwrap();
+        scheduler
+    }
+
+    #[test]
+    fn test_scheduler_feedback_loop() {
+        let scheduler = feedback_loop(&[9, 8, 7, 6, 5]);
+
+        assert_eq!(scheduler.outputs(4).last(), Some(&139629729));
+        assert!((0..5).all(|m| scheduler.is_halted(m)));
+    }
+
+    #[test]
+    fn test_scheduler_is_deterministic() {
+        let first = feedback_loop(&[9, 8, 7, 6, 5]);
+        let second = feedback_loop(&[9, 8, 7, 6, 5]);
+
+        assert_eq!(first.events(), second.events());
+        assert_eq!(
+            &first.events()[..3],
+            &[
+                Event::Input {
+                    machine: 0,
+                    value: 9
+                },
+                Event::Input {
+                    machine: 0,
+                    value: 0
+                },
+                Event::Output {
+                    machine: 0,
+                    value: 5
+                },
+            ]
+        );
+        assert_eq!(first.events().last(), Some(&Event::Halted { machine: 4 }));
+    }
+
+    #[test]
+    fn test_scheduler_deadlock() {
+        let mut scheduler = Scheduler::new();
+
+        // Both machines want input before producing any output
+        let a = scheduler.add(Program::new("a", &[3, 0, 4, 0, 99]), &[]);
+        let b = scheduler.add(Program::new("b", &[3, 0, 4, 0, 99]), &[]);
+        scheduler.connect(a, b);
+        scheduler.connect(b, a);
+
+        assert_eq!(
+            scheduler.run(),
+            Err(IntcodeError::Deadlock {
+                blocked: vec![a, b]
+            })
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::error::IntcodeError;
+use super::{Program, Step};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The order nodes are expanded in
+pub enum Order {
+    BreadthFirst,
+    DepthFirst,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What to do with a node after visiting it
+pub enum Visit {
+    /// Try every candidate input from this node
+    Expand,
+    /// Don't explore past this node
+    Prune,
+    /// End the search, returning this node
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+/// A machine paused waiting for input, or halted
+pub struct Node {
+    pub program: Program,
+    /// Every input given since the start of the search
+    pub inputs: Vec<i64>,
+    /// Outputs produced since the last input
+    pub outputs: Vec<i64>,
+    pub halted: bool,
+}
+
+impl Node {
+    /// Run `program` until it next needs input or halts
+    fn run(mut program: Program, inputs: Vec<i64>) -> Result<Node, IntcodeError> {
+        let mut outputs = Vec::new();
+
+        let halted = loop {
+            match program.step_with(|| None)? {
+                Step::Continue => {}
+                Step::Output(output) => outputs.push(output),
+                Step::NeedsInput => break false,
+                Step::Halted => break true,
+            }
+        };
+
+        Ok(Node {
+            program,
+            inputs,
+            outputs,
+            halted,
+        })
+    }
+
+    /// Give a paused node `input` and run to the next node
+    fn child(&self, input: i64) -> Result<Node, IntcodeError> {
+        let mut program = self.program.clone();
+        let mut inputs = self.inputs.clone();
+
+        program.step_with(|| Some(input))?;
+        inputs.push(input);
+
+        Node::run(program, inputs)
+    }
+}
+
+/// Search the states `program` can reach by answering its input requests
+///
+/// The program runs until it first asks for input, which is the root node.
+/// Each expanded node is forked once per candidate, given that input and
+/// run until it asks again or halts. States already seen, by memory, current
+/// address and relative base, are not visited twice.
+///
+/// `visit` is called once for every new node. Returns the node it stopped
+/// on, or None if every reachable state was visited or pruned.
+pub fn search(
+    program: Program,
+    candidates: &[i64],
+    order: Order,
+    mut visit: impl FnMut(&Node) -> Visit,
+) -> Result<Option<Node>, IntcodeError> {
+    let root = Node::run(program, Vec::new())?;
+    let mut seen = HashSet::new();
+    let mut frontier = VecDeque::new();
+
+    seen.insert(root.program.state_hash());
+    match visit(&root) {
+        Visit::Stop => return Ok(Some(root)),
+        Visit::Prune => return Ok(None),
+        Visit::Expand => frontier.push_back(root),
+    }
+
+    while let Some(node) = match order {
+        Order::BreadthFirst => frontier.pop_front(),
+        Order::DepthFirst => frontier.pop_back(),
+    } {
+        if node.halted {
+            continue;
+        }
+
+        let mut children = Vec::new();
+
+        for &input in candidates {
+            let child = node.child(input)?;
+
+            if !seen.insert(child.program.state_hash()) {
+                continue;
+            }
+
+            match visit(&child) {
+                Visit::Stop => return Ok(Some(child)),
+                Visit::Prune => {}
+                Visit::Expand => children.push(child),
+            }
+        }
+
+        // Depth first takes from the back, so push in reverse to try the
+        // candidates in order
+        if order == Order::DepthFirst {
+            children.reverse();
+        }
+        frontier.extend(children);
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Adds each input to a total and outputs it, halting once the total is 5
+    const COUNTER: &[i64] = &[
+        3, 23, 1, 23, 24, 24, 4, 24, 1008, 24, 5, 25, 1005, 25, 22, 1101, 0, 0, 23, 1105, 1, 0, 99,
+        0, 0, 0,
+    ];
+
+    fn visit(node: &Node) -> Visit {
+        match node.outputs.last() {
+            _ if node.halted => Visit::Stop,
+            Some(total) if *total > 5 => Visit::Prune,
+            _ => Visit::Expand,
+        }
+    }
+
+    fn visited(order: Order) -> (Node, Vec<Vec<i64>>) {
+        let mut visited = Vec::new();
+        let found = search(Program::new("", COUNTER), &[1, 2], order, |node| {
+            visited.push(node.inputs.clone());
+            visit(node)
+        })
+        .unwrap()
+        .unwrap();
+
+        (found, visited)
+    }
+
+    #[test]
+    fn test_breadth_first() {
+        let (found, visited) = visited(Order::BreadthFirst);
+
+        assert_eq!(found.inputs, &[1, 2, 2]);
+        assert_eq!(found.outputs, &[5]);
+        // [1, 1] reaches the same state as [2] so it is never visited
+        assert_eq!(
+            visited,
+            vec![
+                vec![],
+                vec![1],
+                vec![2],
+                vec![1, 2],
+                vec![2, 2],
+                vec![1, 2, 2]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_depth_first() {
+        let (found, visited) = visited(Order::DepthFirst);
+
+        assert_eq!(found.inputs, &[1, 2, 2]);
+        assert_eq!(
+            visited,
+            vec![
+                vec![],
+                vec![1],
+                vec![2],
+                vec![1, 2],
+                vec![1, 2, 1],
+                vec![1, 2, 2]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exhausted() {
+        let found = search(Program::new("", COUNTER), &[3], Order::BreadthFirst, visit).unwrap();
+
+        assert!(found.is_none());
+    }
+}
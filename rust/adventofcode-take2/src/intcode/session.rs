@@ -0,0 +1,507 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::error::IntcodeError;
+use super::io::{ProgramIO, Traffic};
+use super::{Program, Step};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// The inputs and outputs of a single run, in the order they happened
+///
+/// Sessions are saved as text with one event per line: `in 5`, `out 12`,
+/// and a final `halt` if the program halted. Lines starting with `#` are
+/// comments.
+pub struct Session {
+    traffic: Vec<Traffic>,
+    halted: bool,
+}
+
+impl Session {
+    pub fn new(traffic: Vec<Traffic>, halted: bool) -> Session {
+        Session { traffic, halted }
+    }
+
+    pub fn traffic(&self) -> &[Traffic] {
+        &self.traffic
+    }
+
+    /// Whether the recorded program halted, rather than stopping for input
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn outputs(&self) -> Vec<i64> {
+        self.traffic
+            .iter()
+            .filter_map(|t| match t {
+                Traffic::Input(_) => None,
+                Traffic::Output(n) => Some(*n),
+            })
+            .collect()
+    }
+
+    /// Run `program` with `inputs`, recording everything it consumes and produces
+    ///
+    /// Traffic is recorded as it happens, so the session is still usable when
+    /// the run fails, for example when an interactive run runs out of input.
+    pub fn record(
+        &mut self,
+        program: &mut Program,
+        inputs: impl IntoIterator<Item = i64>,
+    ) -> Result<(), IntcodeError> {
+        self.record_with(program, inputs, |_| {})
+    }
+
+    /// Like `record`, also passing each output to `on_output` as it is produced
+    pub fn record_with(
+        &mut self,
+        program: &mut Program,
+        inputs: impl IntoIterator<Item = i64>,
+        mut on_output: impl FnMut(i64),
+    ) -> Result<(), IntcodeError> {
+        let mut inputs = inputs.into_iter();
+
+        loop {
+            let address = program.current_address();
+            let traffic = &mut self.traffic;
+
+            let step = program.step_with(|| {
+                let input = inputs.next()?;
+                traffic.push(Traffic::Input(input));
+                Some(input)
+            })?;
+
+            match step {
+                Step::Continue => {}
+                Step::Output(output) => {
+                    self.traffic.push(Traffic::Output(output));
+                    on_output(output);
+                }
+                Step::NeedsInput => return Err(IntcodeError::InputExhausted { address }),
+                Step::Halted => {
+                    self.halted = true;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for traffic in &self.traffic {
+            match traffic {
+                Traffic::Input(n) => writeln!(f, "in {}", n)?,
+                Traffic::Output(n) => writeln!(f, "out {}", n)?,
+            }
+        }
+
+        if self.halted {
+            writeln!(f, "halt")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Session {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut session = Session::default();
+
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if session.halted {
+                return Err(format!("Line {}: events after halt", number + 1));
+            }
+
+            let mut words = line.split_whitespace();
+            let kind = words.next().unwrap_or_default();
+            let value = words.next().map(|v| {
+                v.parse::<i64>()
+                    .map_err(|_| format!("Line {}: invalid value {}", number + 1, v))
+            });
+
+            match (kind, value, words.next()) {
+                ("in", Some(value), None) => session.traffic.push(Traffic::Input(value?)),
+                ("out", Some(value), None) => session.traffic.push(Traffic::Output(value?)),
+                ("halt", None, None) => session.halted = true,
+                _ => return Err(format!("Line {}: unknown event {}", number + 1, line)),
+            }
+        }
+
+        Ok(session)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The first point where a replay stopped matching its session
+///
+/// `index` is the position in the session's traffic where it happened.
+pub enum ReplayError {
+    /// The program produced a different output than was recorded
+    WrongOutput {
+        index: usize,
+        expected: i64,
+        actual: i64,
+    },
+    /// The program produced an output where an input or the end was recorded
+    UnexpectedOutput {
+        index: usize,
+        expected: Option<Traffic>,
+        actual: i64,
+    },
+    /// The program asked for input where an output or the end was recorded
+    UnexpectedInput {
+        index: usize,
+        expected: Option<Traffic>,
+    },
+    /// The program halted before the rest of the session happened
+    UnexpectedHalt {
+        index: usize,
+        expected: Option<Traffic>,
+    },
+    /// The program failed while replaying
+    Program(IntcodeError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let describe = |expected: &Option<Traffic>| match expected {
+            Some(Traffic::Input(n)) => format!("input {}", n),
+            Some(Traffic::Output(n)) => format!("output {}", n),
+            None => "the end of the session".to_string(),
+        };
+
+        match self {
+            ReplayError::WrongOutput {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Event {}: expected output {} but got {}",
+                index, expected, actual
+            ),
+            ReplayError::UnexpectedOutput {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Event {}: expected {} but got output {}",
+                index,
+                describe(expected),
+                actual
+            ),
+            ReplayError::UnexpectedInput { index, expected } => write!(
+                f,
+                "Event {}: expected {} but the program asked for input",
+                index,
+                describe(expected)
+            ),
+            ReplayError::UnexpectedHalt { index, expected } => write!(
+                f,
+                "Event {}: expected {} but the program halted",
+                index,
+                describe(expected)
+            ),
+            ReplayError::Program(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+impl From<IntcodeError> for ReplayError {
+    fn from(error: IntcodeError) -> Self {
+        ReplayError::Program(error)
+    }
+}
+
+/// Feeds a session's inputs back to a program and checks its outputs
+///
+/// `run` stops at the first divergence. When used as a plain `ProgramIO` the
+/// first divergence is kept for `finish`, and inputs requested out of turn
+/// are answered with 0.
+pub struct ReplayIO {
+    pending: VecDeque<Traffic>,
+    halted: bool,
+    index: usize,
+    error: Option<ReplayError>,
+}
+
+impl ReplayIO {
+    pub fn new(session: &Session) -> ReplayIO {
+        ReplayIO {
+            pending: session.traffic.iter().copied().collect(),
+            halted: session.halted,
+            index: 0,
+            error: None,
+        }
+    }
+
+    /// Replay the session against `program`
+    pub fn run(&mut self, program: &mut Program) -> Result<(), ReplayError> {
+        while self.error.is_none() {
+            match program.step_with(|| self.next_input())? {
+                Step::Continue => {}
+                // Either the session is used up or a divergence was recorded
+                Step::NeedsInput => break,
+                Step::Output(output) => self.check_output(output),
+                Step::Halted => return self.finish_halted(),
+            }
+        }
+
+        self.error.take().map_or(Ok(()), Err)
+    }
+
+    /// Consume the IO after `Program::run`, reporting the first divergence
+    pub fn finish(mut self) -> Result<(), ReplayError> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.finish_halted(),
+        }
+    }
+
+    fn finish_halted(&mut self) -> Result<(), ReplayError> {
+        if !self.pending.is_empty() || !self.halted {
+            return Err(ReplayError::UnexpectedHalt {
+                index: self.index,
+                expected: self.pending.front().copied(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The next recorded input, if the session expects one now
+    ///
+    /// Asking for input once a session that didn't halt is used up is where
+    /// the recording stopped, so that isn't a divergence.
+    fn next_input(&mut self) -> Option<i64> {
+        match self.pending.front().copied() {
+            Some(Traffic::Input(input)) => {
+                self.pending.pop_front();
+                self.index += 1;
+                Some(input)
+            }
+            None if !self.halted => None,
+            expected => {
+                self.diverge(ReplayError::UnexpectedInput {
+                    index: self.index,
+                    expected,
+                });
+                None
+            }
+        }
+    }
+
+    fn check_output(&mut self, actual: i64) {
+        match self.pending.front().copied() {
+            Some(Traffic::Output(expected)) if expected == actual => {
+                self.pending.pop_front();
+                self.index += 1;
+            }
+            Some(Traffic::Output(expected)) => self.diverge(ReplayError::WrongOutput {
+                index: self.index,
+                expected,
+                actual,
+            }),
+            expected => self.diverge(ReplayError::UnexpectedOutput {
+                index: self.index,
+                expected,
+                actual,
+            }),
+        }
+    }
+
+    fn diverge(&mut self, error: ReplayError) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+}
+
+impl ProgramIO for ReplayIO {
+    fn get_next_input(&mut self) -> i64 {
+        self.next_input().unwrap_or_else(|| {
+            // A run can't pause here, so even the end of the session diverges
+            self.diverge(ReplayError::UnexpectedInput {
+                index: self.index,
+                expected: None,
+            });
+            0
+        })
+    }
+
+    fn receive_output(&mut self, output: i64) {
+        self.check_output(output);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Adds pairs of inputs until it reads a zero
+    const ADDER: &[i64] = &[
+        3, 17, 1006, 17, 16, 3, 18, 1, 17, 18, 19, 4, 19, 1105, 1, 0, 99, 0, 0, 0,
+    ];
+
+    fn record(inputs: &[i64]) -> (Session, Result<(), IntcodeError>) {
+        let mut session = Session::default();
+        let result = session.record(&mut Program::new("", ADDER), inputs.to_vec());
+        (session, result)
+    }
+
+    #[test]
+    fn test_record() {
+        let (session, result) = record(&[1, 2, 3, 4, 0]);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            session.traffic(),
+            &[
+                Traffic::Input(1),
+                Traffic::Input(2),
+                Traffic::Output(3),
+                Traffic::Input(3),
+                Traffic::Input(4),
+                Traffic::Output(7),
+                Traffic::Input(0),
+            ]
+        );
+        assert!(session.halted());
+        assert_eq!(session.outputs(), &[3, 7]);
+    }
+
+    #[test]
+    fn test_record_with() {
+        let mut session = Session::default();
+        let mut seen = Vec::new();
+        let result = session.record_with(
+            &mut Program::new("", ADDER),
+            vec![1, 2, 3, 4, 0],
+            |output| seen.push(output),
+        );
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(seen, session.outputs());
+    }
+
+    #[test]
+    fn test_record_partial() {
+        let (session, result) = record(&[1, 2, 3]);
+
+        assert_eq!(result, Err(IntcodeError::InputExhausted { address: 5 }));
+        assert_eq!(session.traffic().len(), 4);
+        assert!(!session.halted());
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let (session, _) = record(&[5, 6, 0]);
+        let text = session.to_string();
+
+        assert_eq!(text, "in 5\nin 6\nout 11\nin 0\nhalt\n");
+        assert_eq!(text.parse::<Session>(), Ok(session));
+        assert_eq!(
+            "# comment\n\nin 1\n".parse::<Session>(),
+            Ok(Session::new(vec![Traffic::Input(1)], false))
+        );
+        assert!("in x".parse::<Session>().is_err());
+        assert!("push 1".parse::<Session>().is_err());
+        assert!("halt\nin 1".parse::<Session>().is_err());
+    }
+
+    #[test]
+    fn test_replay() {
+        let (session, _) = record(&[1, 2, 3, 4, 0]);
+        let (partial, _) = record(&[1, 2, 3]);
+
+        assert_eq!(
+            ReplayIO::new(&session).run(&mut Program::new("", ADDER)),
+            Ok(())
+        );
+        assert_eq!(
+            ReplayIO::new(&partial).run(&mut Program::new("", ADDER)),
+            Ok(())
+        );
+
+        let mut io = ReplayIO::new(&session);
+        Program::new("", ADDER).run(&mut io).unwrap();
+        assert_eq!(io.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_replay_divergence() {
+        let session = Session::new(
+            vec![
+                Traffic::Input(1),
+                Traffic::Input(2),
+                Traffic::Output(4),
+                Traffic::Input(0),
+            ],
+            true,
+        );
+
+        assert_eq!(
+            ReplayIO::new(&session).run(&mut Program::new("", ADDER)),
+            Err(ReplayError::WrongOutput {
+                index: 2,
+                expected: 4,
+                actual: 3
+            })
+        );
+
+        let mut io = ReplayIO::new(&session);
+        Program::new("", ADDER).run(&mut io).unwrap();
+        assert_eq!(
+            io.finish(),
+            Err(ReplayError::WrongOutput {
+                index: 2,
+                expected: 4,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_replay_unexpected_events() {
+        let early = Session::new(vec![Traffic::Input(1), Traffic::Output(1)], true);
+        let halted = Session::new(vec![Traffic::Input(0), Traffic::Input(1)], true);
+        let extra = Session::new(vec![Traffic::Input(1), Traffic::Input(2)], true);
+
+        assert_eq!(
+            ReplayIO::new(&early).run(&mut Program::new("", ADDER)),
+            Err(ReplayError::UnexpectedInput {
+                index: 1,
+                expected: Some(Traffic::Output(1))
+            })
+        );
+        assert_eq!(
+            ReplayIO::new(&halted).run(&mut Program::new("", ADDER)),
+            Err(ReplayError::UnexpectedHalt {
+                index: 1,
+                expected: Some(Traffic::Input(1))
+            })
+        );
+        assert_eq!(
+            ReplayIO::new(&extra).run(&mut Program::new("", ADDER)),
+            Err(ReplayError::UnexpectedOutput {
+                index: 2,
+                expected: None,
+                actual: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,517 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use clap::Clap;
+
+use crate::intcode_server::{serve, ServeArgs};
+use crate::{Command, Output};
+use intcode::debug::Debugger;
+use intcode::inspect;
+use intcode::isa::InstructionSet;
+use intcode::session::{ReplayIO, Session};
+use intcode::Program;
+
+#[derive(Debug, Clap)]
+pub enum Args {
+    /// Run an intcode program from a file
+    Run(RunArgs),
+    /// Print memory as a table of addresses, values and instructions
+    Dump(DumpArgs),
+    /// Show the memory cells changed by a run
+    Diff(DiffArgs),
+    /// Check a program against a session recorded with `run --record`
+    Replay(ReplayArgs),
+    /// Control a VM with JSON lines over stdin and stdout or a Unix socket
+    Serve(ServeArgs),
+}
+
+impl Command for Args {
+    fn execute(&self) -> Result<Output> {
+        match self {
+            Self::Run(args) => run(args),
+            Self::Dump(args) => dump(args),
+            Self::Diff(args) => diff(args),
+            Self::Replay(args) => replay(args),
+            Self::Serve(args) => serve(args),
+        }
+        .map(Output::Text)
+    }
+}
+
+#[derive(Debug, Clap)]
+/// Options for loading and preparing a program
+pub struct ProgramArgs {
+    /// Path to the program
+    path: PathBuf,
+
+    /// Expand memory to the standard size before running
+    #[clap(long)]
+    expand: bool,
+
+    /// Expand memory to this many cells before running
+    #[clap(long, conflicts_with = "expand")]
+    memory_size: Option<i64>,
+
+    /// Set a memory cell before running, as address=value
+    #[clap(long, number_of_values = 1)]
+    patch: Vec<Patch>,
+
+    /// Instruction set to run under: basic, diagnostic or full
+    #[clap(long, default_value = "full")]
+    instruction_set: InstructionSet,
+}
+
+impl ProgramArgs {
+    /// Load the program and apply memory size and patches
+    pub fn load(&self) -> Result<Program> {
+        let mut program = load_program(&self.path)?;
+
+        program.set_instruction_set(self.instruction_set);
+
+        if self.expand {
+            program.expand();
+        } else if let Some(size) = self.memory_size {
+            program.expand_to(size)?;
+        }
+
+        for patch in &self.patch {
+            if patch.address < 0 || patch.address >= program.memory_size() {
+                return Err(anyhow!("Patch address {} is out of range", patch.address));
+            }
+            program.write(patch.address, patch.value);
+        }
+
+        Ok(program)
+    }
+}
+
+#[derive(Debug, Clap)]
+pub struct RunArgs {
+    #[clap(flatten)]
+    program: ProgramArgs,
+
+    /// Comma separated inputs. Inputs are read from stdin when omitted
+    #[clap(long)]
+    input: Option<String>,
+
+    /// How to print outputs: list, ascii or lines
+    #[clap(long, default_value = "list")]
+    output: OutputFormat,
+
+    /// Also print memory once the program halts
+    #[clap(long)]
+    dump_memory: bool,
+
+    /// Save every input and output to this session file
+    #[clap(long)]
+    record: Option<PathBuf>,
+
+    /// Report uninitialized reads, self-modifying code and misaligned jumps
+    #[clap(long)]
+    sanitize: bool,
+
+    /// Wait for a debugger client on this localhost port before running
+    #[clap(long)]
+    debug: Option<u16>,
+}
+
+#[derive(Debug, Clap)]
+pub struct DumpArgs {
+    #[clap(flatten)]
+    program: ProgramArgs,
+
+    /// First address to dump
+    #[clap(long, default_value = "0")]
+    start: i64,
+
+    /// Address to stop before, defaults to the end of memory in use
+    #[clap(long)]
+    end: Option<i64>,
+
+    /// Run the program with these comma separated inputs before dumping
+    #[clap(long)]
+    input: Option<String>,
+}
+
+#[derive(Debug, Clap)]
+pub struct DiffArgs {
+    #[clap(flatten)]
+    program: ProgramArgs,
+
+    /// Comma separated inputs for the run
+    #[clap(long, default_value = "")]
+    input: String,
+
+    /// Compare against a second run with these inputs instead of the initial memory
+    #[clap(long)]
+    against: Option<String>,
+}
+
+#[derive(Debug, Clap)]
+pub struct ReplayArgs {
+    #[clap(flatten)]
+    program: ProgramArgs,
+
+    /// Session file to replay
+    session: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A memory cell to overwrite before running
+struct Patch {
+    address: i64,
+    value: i64,
+}
+
+impl FromStr for Patch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut split = s.splitn(2, '=');
+        let mut next = || {
+            split
+                .next()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .ok_or_else(|| format!("Expected address=value, got {}", s))
+        };
+
+        Ok(Patch {
+            address: next()?,
+            value: next()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    List,
+    Ascii,
+    Lines,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "list" => Ok(OutputFormat::List),
+            "ascii" => Ok(OutputFormat::Ascii),
+            "lines" => Ok(OutputFormat::Lines),
+            _ => Err(format!("Unknown output format: {}", s)),
+        }
+    }
+}
+
+/// Parse comma or whitespace separated values
+pub fn parse_values(text: &str) -> Result<Vec<i64>> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<i64>()
+                .with_context(|| format!("Invalid value: {}", s))
+        })
+        .collect()
+}
+
+/// Load a program from a file of comma separated values
+pub fn load_program(path: &Path) -> Result<Program> {
+    let code = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read {}", path.display()))?;
+
+    Ok(Program::new(
+        &path.display().to_string(),
+        &parse_values(&code).with_context(|| path.display().to_string())?,
+    ))
+}
+
+/// Inputs read from stdin only when the program asks for them
+#[derive(Default)]
+struct StdinInputs {
+    pending: VecDeque<i64>,
+    error: Option<anyhow::Error>,
+}
+
+impl Iterator for StdinInputs {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        while self.pending.is_empty() && self.error.is_none() {
+            let mut line = String::new();
+
+            match std::io::stdin().lock().read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => match parse_values(&line) {
+                    Ok(values) => self.pending.extend(values),
+                    Err(e) => self.error = Some(e),
+                },
+                Err(e) => self.error = Some(e.into()),
+            }
+        }
+
+        self.pending.pop_front()
+    }
+}
+
+/// One output as it appears in `format`, including any separator before it
+fn render_output(output: i64, format: OutputFormat, first: bool) -> String {
+    match (format, first) {
+        (OutputFormat::List, true) | (OutputFormat::Lines, true) => output.to_string(),
+        (OutputFormat::List, false) => format!(",{}", output),
+        (OutputFormat::Lines, false) => format!("\n{}", output),
+        (OutputFormat::Ascii, _) if (0..128).contains(&output) => {
+            (output as u8 as char).to_string()
+        }
+        (OutputFormat::Ascii, _) => format!("\n{}\n", output),
+    }
+}
+
+fn render_outputs(outputs: &[i64], format: OutputFormat) -> String {
+    outputs
+        .iter()
+        .enumerate()
+        .map(|(i, output)| render_output(*output, format, i == 0))
+        .collect()
+}
+
+/// Prints outputs to stdout as soon as they are produced
+struct OutputPrinter {
+    format: OutputFormat,
+    printed: bool,
+}
+
+impl OutputPrinter {
+    fn print(&mut self, output: i64) {
+        let mut stdout = std::io::stdout();
+
+        // Ignore errors like a closed pipe, as the run itself can carry on
+        let _ = write!(
+            stdout,
+            "{}",
+            render_output(output, self.format, !self.printed)
+        );
+        let _ = stdout.flush();
+        self.printed = true;
+    }
+}
+
+/// Number of cells up to and including the last non-zero one
+fn used_memory(memory: &[i64]) -> usize {
+    memory.iter().rposition(|v| *v != 0).map_or(0, |i| i + 1)
+}
+
+fn render_memory(memory: &[i64]) -> String {
+    // Expanded memory is mostly zeros, so stop at the last cell in use
+    memory[..used_memory(memory)]
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Run `program` until it halts with fixed inputs
+fn run_with_inputs(program: &mut Program, inputs: &str) -> Result<Vec<i64>> {
+    Ok(program
+        .outputs(parse_values(inputs)?)
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+fn run(args: &RunArgs) -> Result<String> {
+    let mut program = args.program.load()?;
+
+    if args.sanitize {
+        program.enable_sanitizer();
+    }
+
+    if let Some(port) = args.debug {
+        let debugger = Debugger::listen(("127.0.0.1", port))
+            .with_context(|| format!("Unable to listen on port {}", port))?;
+
+        eprintln!("Waiting for a debugger on {}", debugger.local_addr());
+        program.attach_debugger(debugger);
+    }
+
+    let (outputs, streamed) = match (&args.input, &args.record) {
+        (Some(input), None) => (run_with_inputs(&mut program, input)?, false),
+        (Some(input), Some(path)) => (
+            record(&mut program, parse_values(input)?, path, |_| {})?,
+            false,
+        ),
+        (None, record_path) => {
+            // Print outputs as they come, so interactive programs show their
+            // prompts before waiting on stdin
+            let mut stdin = StdinInputs::default();
+            let mut printer = OutputPrinter {
+                format: args.output,
+                printed: false,
+            };
+            let finished = match record_path {
+                Some(path) => {
+                    record(&mut program, &mut stdin, path, |o| printer.print(o)).map(|_| ())
+                }
+                None => program
+                    .outputs(&mut stdin)
+                    .try_for_each(|output| output.map(|o| printer.print(o)))
+                    .map_err(anyhow::Error::from),
+            };
+
+            if printer.printed {
+                println!();
+            }
+            if let Some(e) = stdin.error {
+                return Err(e.context("Unable to read input from stdin"));
+            }
+            finished?;
+            (Vec::new(), printer.printed)
+        }
+    };
+
+    let mut sections = vec![render_outputs(&outputs, args.output)];
+
+    if args.dump_memory {
+        sections.push(format!("Memory:\n{}", render_memory(&program.dump())));
+    }
+
+    if args.sanitize {
+        let mut report = "Sanitizer:".to_string();
+        for line in program.sanitizer_reports() {
+            report.push_str(&format!("\n{}", line));
+        }
+        if program.sanitizer_reports().is_empty() {
+            report.push_str(" no reports");
+        }
+        sections.push(report);
+    }
+
+    sections.retain(|section| !section.is_empty());
+    let result = sections.join("\n\n");
+
+    // Streamed outputs have already ended their line
+    Ok(match streamed && !result.is_empty() {
+        true => format!("\n{}", result),
+        false => result,
+    })
+}
+
+/// Run `program`, saving the session to `path` even if the run fails
+fn record(
+    program: &mut Program,
+    inputs: impl IntoIterator<Item = i64>,
+    path: &Path,
+    on_output: impl FnMut(i64),
+) -> Result<Vec<i64>> {
+    let mut session = Session::default();
+    let result = session.record_with(program, inputs, on_output);
+
+    std::fs::write(path, session.to_string())
+        .with_context(|| format!("Unable to write {}", path.display()))?;
+    result?;
+
+    Ok(session.outputs())
+}
+
+fn dump(args: &DumpArgs) -> Result<String> {
+    let mut program = args.program.load()?;
+
+    if let Some(input) = &args.input {
+        run_with_inputs(&mut program, input)?;
+    }
+
+    let end = args
+        .end
+        .unwrap_or_else(|| used_memory(&program.dump()) as i64);
+
+    Ok(inspect::render_dump(&inspect::dump(
+        &program,
+        args.start..end,
+    )))
+}
+
+fn diff(args: &DiffArgs) -> Result<String> {
+    let mut program = args.program.load()?;
+
+    let before = match &args.against {
+        Some(against) => {
+            let mut other = program.clone();
+            run_with_inputs(&mut other, against)?;
+            other.dump()
+        }
+        None => program.dump(),
+    };
+
+    run_with_inputs(&mut program, &args.input)?;
+
+    Ok(inspect::render_diff(&inspect::diff(
+        &before,
+        &program.dump(),
+    )))
+}
+
+fn replay(args: &ReplayArgs) -> Result<String> {
+    let mut program = args.program.load()?;
+    let session = std::fs::read_to_string(&args.session)
+        .with_context(|| format!("Unable to read {}", args.session.display()))?
+        .parse::<Session>()
+        .map_err(|e| anyhow!(e))
+        .with_context(|| args.session.display().to_string())?;
+
+    ReplayIO::new(&session)
+        .run(&mut program)
+        .with_context(|| format!("Replay of {} diverged", args.session.display()))?;
+
+    Ok(format!(
+        "Replayed {} events from {}",
+        session.traffic().len(),
+        args.session.display()
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_patch() {
+        assert_eq!(
+            "1=12".parse::<Patch>(),
+            Ok(Patch {
+                address: 1,
+                value: 12
+            })
+        );
+        assert_eq!(
+            "2 = -3".parse::<Patch>(),
+            Ok(Patch {
+                address: 2,
+                value: -3
+            })
+        );
+        assert!("2".parse::<Patch>().is_err());
+        assert!("a=1".parse::<Patch>().is_err());
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(parse_values("1,2, 3\n").unwrap(), vec![1, 2, 3]);
+        assert!(parse_values("").unwrap().is_empty());
+        assert!(parse_values("1,x").is_err());
+    }
+
+    #[test]
+    fn test_render_outputs() {
+        assert_eq!(render_outputs(&[1, 2, 3], OutputFormat::List), "1,2,3");
+        assert_eq!(render_outputs(&[1, 2, 3], OutputFormat::Lines), "1\n2\n3");
+        assert_eq!(
+            render_outputs(&[72, 105, 10, 1000], OutputFormat::Ascii),
+            "Hi\n\n1000\n"
+        );
+    }
+
+    #[test]
+    fn test_render_memory() {
+        assert_eq!(render_memory(&[1, 0, 2, 0, 0]), "1,0,2");
+        assert_eq!(render_memory(&[0, 0]), "");
+    }
+}
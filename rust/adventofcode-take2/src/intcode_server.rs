@@ -0,0 +1,452 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use clap::Clap;
+use serde::{Deserialize, Serialize};
+
+use intcode::{Program, Step};
+
+use crate::intcode_cli::{load_program, parse_values};
+
+#[derive(Debug, Clap)]
+pub struct ServeArgs {
+    /// Listen on this Unix socket instead of stdin and stdout
+    #[clap(long)]
+    socket: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case", deny_unknown_fields)]
+/// A single line sent to the server
+enum Request {
+    /// Replace the program, from either `code` or a file at `path`
+    Load {
+        code: Option<String>,
+        path: Option<PathBuf>,
+        memory_size: Option<i64>,
+    },
+    /// Queue inputs for the program
+    Input { values: Vec<i64> },
+    /// Run until the program needs input or halts, or for at most `max_steps`
+    Run { max_steps: Option<usize> },
+    /// Execute a single instruction
+    Step,
+    /// Read `count` cells starting at `address`
+    Peek { address: i64, count: Option<i64> },
+    /// Write `values` starting at `address`
+    Poke { address: i64, values: Vec<i64> },
+    /// Save the program and queued inputs, returning a snapshot id
+    Snapshot,
+    /// Go back to a saved snapshot
+    Restore { snapshot: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// Where the program stopped after `run` or `step`
+enum Status {
+    /// Stopped by `max_steps` or after a single step, and can keep going
+    Running,
+    NeedsInput,
+    Halted,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+/// The reply to a request, written as a single line
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<Status>,
+    /// Address of the next instruction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outputs: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    values: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot: Option<usize>,
+}
+
+impl Response {
+    fn ok() -> Response {
+        Response {
+            ok: true,
+            ..Response::default()
+        }
+    }
+
+    fn error(error: anyhow::Error) -> Response {
+        Response {
+            error: Some(format!("{:#}", error)),
+            ..Response::default()
+        }
+    }
+}
+
+#[derive(Default)]
+/// A VM controlled one request at a time
+struct Server {
+    program: Option<Program>,
+    inputs: VecDeque<i64>,
+    snapshots: Vec<(Program, VecDeque<i64>)>,
+}
+
+impl Server {
+    /// Handle one line of JSON, never failing
+    ///
+    /// A panic while handling the request becomes an error response, so that
+    /// one bad program can't take down a long-lived server.
+    fn handle_line(&mut self, line: &str) -> Response {
+        serde_json::from_str::<Request>(line)
+            .map_err(|e| anyhow!("Invalid request: {}", e))
+            .and_then(|request| {
+                catch_unwind(AssertUnwindSafe(|| self.handle(request)))
+                    .unwrap_or_else(|_| Err(anyhow!("The VM panicked")))
+            })
+            .unwrap_or_else(Response::error)
+    }
+
+    fn handle(&mut self, request: Request) -> Result<Response> {
+        match request {
+            Request::Load {
+                code,
+                path,
+                memory_size,
+            } => {
+                let mut program = match (code, path) {
+                    (Some(code), None) => Program::new("server", &parse_values(&code)?),
+                    (None, Some(path)) => load_program(&path)?,
+                    _ => return Err(anyhow!("Load needs exactly one of code or path")),
+                };
+
+                if let Some(size) = memory_size {
+                    program.expand_to(size)?;
+                }
+
+                self.program = Some(program);
+                self.inputs.clear();
+                self.snapshots.clear();
+                Ok(Response::ok())
+            }
+            Request::Input { values } => {
+                self.inputs.extend(values);
+                Ok(Response::ok())
+            }
+            Request::Run { max_steps } => self.run(max_steps),
+            Request::Step => self.run(Some(1)),
+            Request::Peek { address, count } => {
+                let program = self.program()?;
+                let count = count.unwrap_or(1);
+
+                if count < 1 {
+                    return Err(anyhow!("Count must be at least 1, not {}", count));
+                }
+                let end = cells_end(program, address, count)?;
+
+                Ok(Response {
+                    values: Some((address..end).map(|a| program.read(a)).collect()),
+                    ..Response::ok()
+                })
+            }
+            Request::Poke { address, values } => {
+                let program = self.program_mut()?;
+                cells_end(program, address, values.len() as i64)?;
+
+                for (offset, value) in values.into_iter().enumerate() {
+                    program.write(address + offset as i64, value);
+                }
+                Ok(Response::ok())
+            }
+            Request::Snapshot => {
+                let program = self.program()?.clone();
+
+                self.snapshots.push((program, self.inputs.clone()));
+                Ok(Response {
+                    snapshot: Some(self.snapshots.len() - 1),
+                    ..Response::ok()
+                })
+            }
+            Request::Restore { snapshot } => {
+                let (program, inputs) = self
+                    .snapshots
+                    .get(snapshot)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Unknown snapshot {}", snapshot))?;
+
+                self.program = Some(program);
+                self.inputs = inputs;
+                Ok(Response::ok())
+            }
+        }
+    }
+
+    fn run(&mut self, max_steps: Option<usize>) -> Result<Response> {
+        let inputs = &mut self.inputs;
+        let program = self
+            .program
+            .as_mut()
+            .ok_or_else(|| anyhow!("No program loaded"))?;
+        let mut outputs = Vec::new();
+        let mut steps = 0;
+
+        let status = loop {
+            if max_steps.is_some_and(|max| steps >= max) {
+                break Status::Running;
+            }
+
+            match program.step_with(|| inputs.pop_front())? {
+                Step::Continue => {}
+                Step::Output(output) => outputs.push(output),
+                Step::NeedsInput => break Status::NeedsInput,
+                Step::Halted => break Status::Halted,
+            }
+            steps += 1;
+        };
+
+        Ok(Response {
+            status: Some(status),
+            address: Some(program.current_address()),
+            outputs: Some(outputs),
+            ..Response::ok()
+        })
+    }
+
+    fn program(&self) -> Result<&Program> {
+        self.program
+            .as_ref()
+            .ok_or_else(|| anyhow!("No program loaded"))
+    }
+
+    fn program_mut(&mut self) -> Result<&mut Program> {
+        self.program
+            .as_mut()
+            .ok_or_else(|| anyhow!("No program loaded"))
+    }
+
+    /// Answer each request line from `reader` with a response line on `writer`
+    fn serve(&mut self, reader: impl BufRead, mut writer: impl Write) -> Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = self.handle_line(&line);
+            serde_json::to_writer(&mut writer, &response)?;
+            writeln!(writer)?;
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The end of `count` cells from `address`, if they are all inside memory
+fn cells_end(program: &Program, address: i64, count: i64) -> Result<i64> {
+    match address.checked_add(count) {
+        Some(end) if address >= 0 && end <= program.memory_size() => Ok(end),
+        Some(end) => Err(anyhow!("#{}..#{} is outside of memory", address, end)),
+        None => Err(anyhow!(
+            "#{} plus {} cells is outside of memory",
+            address,
+            count
+        )),
+    }
+}
+
+/// Serve on stdin and stdout, or on a Unix socket
+///
+/// Socket clients are served one at a time and share the same VM, so a
+/// tool can reconnect without losing state.
+pub fn serve(args: &ServeArgs) -> Result<String> {
+    let mut server = Server::default();
+
+    match &args.socket {
+        None => {
+            let stdin = std::io::stdin();
+            server.serve(stdin.lock(), std::io::stdout())?;
+        }
+        Some(path) => serve_socket(&mut server, path)?,
+    }
+
+    Ok(String::new())
+}
+
+#[cfg(unix)]
+fn serve_socket(server: &mut Server, path: &std::path::Path) -> Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Unable to listen on {}", path.display()))?;
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let reader = BufReader::new(stream.try_clone()?);
+
+        // A client going away shouldn't stop the server
+        if let Err(e) = server.serve(reader, stream) {
+            tracing::warn!("Client disconnected: {:#}", e);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_socket(_server: &mut Server, _path: &std::path::Path) -> Result<()> {
+    Err(anyhow!("Unix sockets are not supported on this platform"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn exchange(server: &mut Server, request: &str) -> String {
+        serde_json::to_string(&server.handle_line(request)).unwrap()
+    }
+
+    #[test]
+    fn test_run_and_input() {
+        let mut server = Server::default();
+
+        assert_eq!(
+            exchange(
+                &mut server,
+                r#"{"cmd": "load", "code": "3,11,3,12,2,11,12,13,4,13,99,0,0,0"}"#
+            ),
+            r#"{"ok":true}"#
+        );
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "run"}"#),
+            r#"{"ok":true,"status":"needs_input","address":0,"outputs":[]}"#
+        );
+        exchange(&mut server, r#"{"cmd": "input", "values": [3, 4]}"#);
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "step"}"#),
+            r#"{"ok":true,"status":"running","address":2,"outputs":[]}"#
+        );
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "run"}"#),
+            r#"{"ok":true,"status":"halted","address":10,"outputs":[12]}"#
+        );
+    }
+
+    #[test]
+    fn test_peek_poke_snapshot() {
+        let mut server = Server::default();
+
+        exchange(&mut server, r#"{"cmd": "load", "code": "1,0,0,0,99"}"#);
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "snapshot"}"#),
+            r#"{"ok":true,"snapshot":0}"#
+        );
+        exchange(
+            &mut server,
+            r#"{"cmd": "poke", "address": 1, "values": [4]}"#,
+        );
+        exchange(&mut server, r#"{"cmd": "run"}"#);
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "peek", "address": 0, "count": 2}"#),
+            r#"{"ok":true,"values":[100,4]}"#
+        );
+
+        exchange(&mut server, r#"{"cmd": "restore", "snapshot": 0}"#);
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "peek", "address": 0, "count": 2}"#),
+            r#"{"ok":true,"values":[1,0]}"#
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut server = Server::default();
+
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "run"}"#),
+            r#"{"ok":false,"error":"No program loaded"}"#
+        );
+        assert!(exchange(&mut server, r#"{"cmd": "jump"}"#).starts_with(r#"{"ok":false"#));
+        assert!(exchange(&mut server, "not json").starts_with(r#"{"ok":false"#));
+
+        exchange(&mut server, r#"{"cmd": "load", "code": "42"}"#);
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "peek", "address": 1}"#),
+            r##"{"ok":false,"error":"#1..#2 is outside of memory"}"##
+        );
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "run"}"#),
+            r##"{"ok":false,"error":"Unknown opcode 42 at #0"}"##
+        );
+
+        exchange(&mut server, r#"{"cmd": "load", "code": "1101,1,1,100,99"}"#);
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "run"}"#),
+            r##"{"ok":false,"error":"Address #100 is outside of memory"}"##
+        );
+        assert_eq!(
+            exchange(
+                &mut server,
+                r#"{"cmd": "peek", "address": 1, "count": 9223372036854775807}"#
+            ),
+            r##"{"ok":false,"error":"#1 plus 9223372036854775807 cells is outside of memory"}"##
+        );
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "peek", "address": 1, "count": 0}"#),
+            r#"{"ok":false,"error":"Count must be at least 1, not 0"}"#
+        );
+        assert!(exchange(
+            &mut server,
+            r#"{"cmd": "poke", "address": 9223372036854775807, "values": [1]}"#
+        )
+        .starts_with(r#"{"ok":false"#));
+    }
+
+    #[test]
+    fn test_load_memory_size() {
+        let mut server = Server::default();
+
+        assert_eq!(
+            exchange(
+                &mut server,
+                r#"{"cmd": "load", "code": "1,0,0,0,99", "memory_size": -5}"#
+            ),
+            r#"{"ok":false,"error":"Memory size -5 is smaller than the 5 cells already in use"}"#
+        );
+        assert_eq!(
+            exchange(
+                &mut server,
+                r#"{"cmd": "load", "code": "1,0,0,0,99", "memory_size": 3}"#
+            ),
+            r#"{"ok":false,"error":"Memory size 3 is smaller than the 5 cells already in use"}"#
+        );
+        exchange(
+            &mut server,
+            r#"{"cmd": "load", "code": "1,0,0,0,99", "memory_size": 8}"#,
+        );
+        assert_eq!(
+            exchange(&mut server, r#"{"cmd": "peek", "address": 5, "count": 3}"#),
+            r#"{"ok":true,"values":[0,0,0]}"#
+        );
+    }
+
+    #[test]
+    fn test_serve() {
+        let mut server = Server::default();
+        let mut output = Vec::new();
+        let input = "{\"cmd\": \"load\", \"code\": \"104,7,99\"}\n\n{\"cmd\": \"run\"}\n";
+
+        server.serve(input.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"ok\":true}\n{\"ok\":true,\"status\":\"halted\",\"address\":2,\"outputs\":[7]}\n"
+        );
+    }
+}
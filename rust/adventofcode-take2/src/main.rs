@@ -1,24 +1,30 @@
+mod answer;
 #[macro_use]
 mod args;
+mod bench;
 mod command;
-mod intcode;
+mod inputs;
+mod intcode_cli;
+mod intcode_server;
+mod output;
 mod point;
+mod runner;
+mod scaffold;
+mod solution;
+mod verify;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Result};
 use clap::Clap;
 use tracing_subscriber::FmtSubscriber;
 
-use crate::command::Command;
+use crate::answer::Answer;
+use crate::command::{Command, Output};
+use crate::output::Format;
+use crate::solution::Solution;
 
-// NOTE: Each solution module must be added here
-solution!(day01, day02, day03, day04, day05, day06, day07, day09, day11);
-
-fn input(name: &str) -> Result<String> {
-    Ok(
-        std::fs::read_to_string(format!("inputs/{}.txt", name))
-            .with_context(|| name.to_string())?,
-    )
-}
+// Every src/dayNN.rs, found by the build script
+include!(concat!(env!("OUT_DIR"), "/solutions.rs"));
+use solutions::Solutions;
 
 fn main() -> Result<()> {
     let args = args::Args::parse();
@@ -27,9 +33,49 @@ fn main() -> Result<()> {
         .with_env_filter(args.env_filter())
         .init();
 
-    let solution = args.command.execute()?;
+    args.inputs.install()?;
+
+    let single = matches!(args.command, args::Commands::Solution(_));
+
+    match args.command.execute()? {
+        Output::Results(results) => {
+            match (single, args.format, results.first()) {
+                (true, Format::Text, Some(result)) if result.is_ok() => {
+                    println!("Solution:\n{}", result.outcome.text())
+                }
+                // A failed solution is reported as the error below
+                (true, Format::Text, _) => {}
+                (_, format, _) => println!("{}", output::render(&results, format)),
+            }
+
+            let failures = results.iter().filter(|r| !r.is_ok()).count();
+            match results.iter().find(|r| !r.is_ok()) {
+                Some(failed) if single => return Err(anyhow!("{}", failed.outcome.text())),
+                // Each failure is already in the table
+                Some(_) => {
+                    return Err(anyhow!(
+                        "{} of {} solutions failed",
+                        failures,
+                        results.len()
+                    ))
+                }
+                None => {}
+            }
+        }
+        Output::Verified(report) => {
+            println!("{}", output::render_verdicts(&report.checks, args.format));
 
-    println!("Solution:\n{}", solution);
+            // Failures go to the exit status so that scripts can use it
+            match (report.failed(), args.format) {
+                (true, _) => return Err(anyhow!("{}", report.summary())),
+                (false, Format::Text) => println!("{}", report.summary()),
+                (false, _) => eprintln!("{}", report.summary()),
+            }
+        }
+        // Commands like the server write their own output as they go
+        Output::Text(text) if text.is_empty() => {}
+        Output::Text(text) => println!("{}", text),
+    }
 
     Ok(())
 }
@@ -0,0 +1,322 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::answer::Answer;
+use crate::runner::{Outcome, SolutionResult};
+use crate::verify::Verdict;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How solution results are printed
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("Unknown format {}, expected text, json or csv", s)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+/// One result as it appears in JSON and CSV
+struct Record<'a> {
+    day: u32,
+    part: u32,
+    input: &'a str,
+    status: &'a str,
+    answer: Option<&'a Answer>,
+    error: Option<&'a str>,
+    parse_ms: f64,
+    elapsed_ms: f64,
+}
+
+impl<'a> From<&'a SolutionResult> for Record<'a> {
+    fn from(result: &'a SolutionResult) -> Self {
+        let (answer, error) = match &result.outcome {
+            Outcome::Solved(answer) => (Some(answer), None),
+            Outcome::Failed(error) | Outcome::Panicked(error) => (None, Some(error.as_str())),
+        };
+
+        Record {
+            day: result.day,
+            part: result.part,
+            input: &result.input,
+            status: result.outcome.status(),
+            answer,
+            error,
+            parse_ms: result.parse.as_secs_f64() * 1000.0,
+            elapsed_ms: result.elapsed.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+pub fn render(results: &[SolutionResult], format: Format) -> String {
+    match format {
+        Format::Text => render_table(results),
+        Format::Json => {
+            let records = results.iter().map(Record::from).collect::<Vec<_>>();
+            serde_json::to_string_pretty(&records).expect("Records always serialize")
+        }
+        Format::Csv => render_csv(results),
+    }
+}
+
+#[derive(Debug, Serialize)]
+/// One checked result as it appears in JSON and CSV
+struct VerdictRecord<'a> {
+    #[serde(flatten)]
+    result: Record<'a>,
+    verdict: &'a str,
+    details: Option<&'a str>,
+}
+
+/// Print the verdicts from `verify` in `format`
+pub fn render_verdicts(checks: &[(SolutionResult, Verdict)], format: Format) -> String {
+    let records = checks.iter().map(|(result, verdict)| VerdictRecord {
+        result: Record::from(result),
+        verdict: verdict.label(),
+        details: verdict.details(),
+    });
+
+    match format {
+        Format::Text => checks
+            .iter()
+            .map(|(result, verdict)| {
+                let mut text = format!(
+                    "day {} part {} ({}): {}",
+                    result.day,
+                    result.part,
+                    result.input,
+                    verdict.label()
+                );
+                for line in verdict.details().unwrap_or_default().lines() {
+                    text.push_str("\n    ");
+                    text.push_str(line);
+                }
+                text
+            })
+            .join("\n"),
+        Format::Json => serde_json::to_string_pretty(&records.collect::<Vec<_>>())
+            .expect("Records always serialize"),
+        Format::Csv => {
+            let mut csv = format!("{},verdict,details", CSV_HEADER);
+
+            for record in records {
+                let mut fields = csv_fields(&record.result);
+                fields.push(record.verdict.to_string());
+                fields.push(csv_field(record.details.unwrap_or_default()));
+
+                csv.push('\n');
+                csv.push_str(&fields.join(","));
+            }
+
+            csv
+        }
+    }
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn render_table(results: &[SolutionResult]) -> String {
+    let times = results
+        .iter()
+        .map(|r| (milliseconds(r.parse), milliseconds(r.elapsed)))
+        .collect::<Vec<_>>();
+    let parse_width = times.iter().map(|t| t.0.len()).max().unwrap_or(0).max(5);
+    let solve_width = times.iter().map(|t| t.1.len()).max().unwrap_or(0).max(5);
+    let mut table = format!(
+        "Day  Part  Status  {:>parse$}  {:>solve$}  Answer\n",
+        "Parse",
+        "Solve",
+        parse = parse_width,
+        solve = solve_width
+    );
+
+    for (result, (parse, solve)) in results.iter().zip(times) {
+        let prefix = format!(
+            "{:>3}  {:>4}  {:<6}  {:>parse_width$}  {:>solve_width$}  ",
+            result.day,
+            result.part,
+            result.outcome.status(),
+            parse,
+            solve,
+        );
+        let text = result.outcome.text();
+        let mut lines = text.lines();
+
+        table.push_str(&prefix);
+        table.push_str(lines.next().unwrap_or_default());
+        table.push('\n');
+
+        // Line up multi-line answers under the first line
+        for line in lines {
+            table.push_str(&" ".repeat(prefix.len()));
+            table.push_str(line);
+            table.push('\n');
+        }
+    }
+
+    table.trim_end().to_string()
+}
+
+/// Quote `field` if it would otherwise break the row
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+const CSV_HEADER: &str = "day,part,input,status,answer,error,parse_ms,elapsed_ms";
+
+fn csv_fields(record: &Record) -> Vec<String> {
+    vec![
+        record.day.to_string(),
+        record.part.to_string(),
+        csv_field(record.input),
+        record.status.to_string(),
+        csv_field(&record.answer.map(Answer::to_string).unwrap_or_default()),
+        csv_field(record.error.unwrap_or_default()),
+        format!("{:.3}", record.parse_ms),
+        format!("{:.3}", record.elapsed_ms),
+    ]
+}
+
+fn render_csv(results: &[SolutionResult]) -> String {
+    let mut csv = CSV_HEADER.to_string();
+
+    for record in results.iter().map(Record::from) {
+        csv.push('\n');
+        csv.push_str(&csv_fields(&record).join(","));
+    }
+
+    csv
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn results() -> Vec<SolutionResult> {
+        vec![
+            SolutionResult {
+                day: 1,
+                part: 1,
+                input: "day01".to_string(),
+                outcome: Outcome::Solved(Answer::Number(42)),
+                parse: Duration::from_millis(2),
+                elapsed: Duration::from_micros(1500),
+            },
+            SolutionResult {
+                day: 1,
+                part: 2,
+                input: "day01".to_string(),
+                outcome: Outcome::Failed("no input, sorry".to_string()),
+                parse: Duration::from_millis(2),
+                elapsed: Duration::from_millis(12),
+            },
+            SolutionResult {
+                day: 2,
+                part: 1,
+                input: "inline".to_string(),
+                outcome: Outcome::Solved(Answer::Grid(vec!["#.".to_string(), ".#".to_string()])),
+                parse: Duration::from_micros(250),
+                elapsed: Duration::from_millis(0),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_text() {
+        assert_eq!(
+            render(&results(), Format::Text),
+            [
+                "Day  Part  Status   Parse    Solve  Answer",
+                "  1     1  ok      2.00ms   1.50ms  42",
+                "  1     2  error   2.00ms  12.00ms  no input, sorry",
+                "  2     1  ok      0.25ms   0.00ms  #.",
+                "                                    .#",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&results(), Format::Json)).unwrap();
+
+        assert_eq!(json[0]["answer"], 42);
+        assert_eq!(json[0]["error"], serde_json::Value::Null);
+        assert_eq!(json[1]["status"], "error");
+        assert_eq!(json[1]["error"], "no input, sorry");
+        assert_eq!(json[2]["answer"][1], ".#");
+        assert_eq!(json[2]["elapsed_ms"], 0.0);
+    }
+
+    #[test]
+    fn test_render_verdicts() {
+        let checks = results()
+            .into_iter()
+            .zip(vec![
+                Verdict::Pass,
+                Verdict::Fail("- 1\n+ 2".to_string()),
+                Verdict::Missing,
+            ])
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            render_verdicts(&checks, Format::Text),
+            [
+                "day 1 part 1 (day01): pass",
+                "day 1 part 2 (day01): fail",
+                "    - 1",
+                "    + 2",
+                "day 2 part 1 (inline): missing",
+            ]
+            .join("\n")
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_verdicts(&checks, Format::Json)).unwrap();
+        assert_eq!(json[0]["answer"], 42);
+        assert_eq!(json[0]["verdict"], "pass");
+        assert_eq!(json[1]["details"], "- 1\n+ 2");
+        assert_eq!(json[2]["details"], serde_json::Value::Null);
+
+        let csv = render_verdicts(&checks, Format::Csv);
+        assert!(csv.starts_with(
+            "day,part,input,status,answer,error,parse_ms,elapsed_ms,verdict,details\n"
+        ));
+        assert!(csv.ends_with("2,1,inline,ok,\"#.\n.#\",,0.250,0.000,missing,"));
+    }
+
+    #[test]
+    fn test_render_csv() {
+        assert_eq!(
+            render(&results(), Format::Csv),
+            [
+                "day,part,input,status,answer,error,parse_ms,elapsed_ms",
+                "1,1,day01,ok,42,,2.000,1.500",
+                "1,2,day01,error,,\"no input, sorry\",2.000,12.000",
+                "2,1,inline,ok,\"#.\n.#\",,0.250,0.000",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::ops;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Point {
     pub x: i64,
     pub y: i64,
@@ -17,12 +17,6 @@ impl Point {
     }
 }
 
-impl Default for Point {
-    fn default() -> Self {
-        Point { x: 0, y: 0 }
-    }
-}
-
 impl ops::Add<Point> for Point {
     type Output = Self;
 
@@ -0,0 +1,266 @@
+use std::cell::Cell;
+use std::ops::RangeInclusive;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use clap::Clap;
+
+use crate::inputs::source_name;
+use crate::solution::{Entry, Part};
+use crate::{Answer, Output, Solutions};
+
+#[derive(Debug, Clap)]
+pub struct AllArgs {
+    /// Only run these days, either one day like `5` or an inclusive range like `1..7`
+    #[clap(long)]
+    days: Option<DayRange>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Days to run, including both ends
+pub struct DayRange(RangeInclusive<u32>);
+
+impl FromStr for DayRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |day: &str| {
+            day.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid day {:?}", day))
+        };
+
+        let (start, end) = match s.find("..") {
+            Some(split) => {
+                let end = &s[split + 2..];
+                let end = end.strip_prefix('=').unwrap_or(end);
+                (parse(&s[..split])?, parse(end)?)
+            }
+            None => (parse(s)?, parse(s)?),
+        };
+
+        if start > end {
+            return Err(format!("{}..{} is empty", start, end));
+        }
+
+        Ok(DayRange(start..=end))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// How a single solution finished
+pub enum Outcome {
+    Solved(Answer),
+    Failed(String),
+    Panicked(String),
+}
+
+impl Outcome {
+    pub fn status(&self) -> &'static str {
+        match self {
+            Outcome::Solved(_) => "ok",
+            Outcome::Failed(_) => "error",
+            Outcome::Panicked(_) => "panic",
+        }
+    }
+
+    /// The answer, or what went wrong
+    pub fn text(&self) -> String {
+        match self {
+            Outcome::Solved(answer) => answer.to_string(),
+            Outcome::Failed(text) | Outcome::Panicked(text) => text.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// How one part of one day went
+pub struct SolutionResult {
+    pub day: u32,
+    pub part: u32,
+    /// Where the input came from, as given by `inputs::source_name`
+    pub input: String,
+    pub outcome: Outcome,
+    /// Time spent parsing the input, which both parts of a day share
+    pub parse: Duration,
+    /// Time spent solving this part
+    pub elapsed: Duration,
+}
+
+impl SolutionResult {
+    pub fn is_ok(&self) -> bool {
+        matches!(self.outcome, Outcome::Solved(_))
+    }
+}
+
+thread_local! {
+    /// Whether this thread is inside `catch`
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Keep panics inside `catch` quiet, passing any others to the usual hook
+///
+/// The hook is process wide, so it is installed once rather than swapped
+/// around each call, which would race between threads.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !CATCHING.with(Cell::get) {
+                hook(info);
+            }
+        }));
+    });
+}
+
+/// Call `f`, turning errors and panics into an `Outcome`
+///
+/// Panics are returned without being printed.
+pub fn catch<T>(f: impl FnOnce() -> Result<T>) -> Result<T, Outcome> {
+    install_panic_hook();
+
+    let outer = CATCHING.with(|catching| catching.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|catching| catching.set(outer));
+
+    match result {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(Outcome::Failed(format!("{:#}", e))),
+        Err(payload) => Err(Outcome::Panicked(
+            payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string()),
+        )),
+    }
+}
+
+/// Parse the day out of a module name like `day07`
+pub fn day_number(module: &str) -> u32 {
+    module
+        .trim_start_matches("day")
+        .parse()
+        .expect("Solution modules are named dayNN")
+}
+
+/// Run `parts` of a day, parsing its input once for all of them
+///
+/// With `--example`, parts the example has no answer for fail without running.
+pub fn run_day(entry: Entry, parts: &[Part]) -> Vec<SolutionResult> {
+    let input = source_name(&entry.name());
+    let (runnable, rejected): (Vec<_>, Vec<_>) = parts
+        .iter()
+        .map(|&part| (part, entry.check_example(part)))
+        .partition(|(_, check)| check.is_ok());
+    let runnable = runnable
+        .into_iter()
+        .map(|(part, _)| part)
+        .collect::<Vec<_>>();
+
+    let mut results = run_input(entry, &input, entry.input(), &runnable);
+    results.extend(rejected.into_iter().map(|(part, check)| SolutionResult {
+        day: entry.day,
+        part: part.number(),
+        input: input.clone(),
+        outcome: Outcome::Failed(format!("{:#}", check.unwrap_err())),
+        parse: Duration::ZERO,
+        elapsed: Duration::ZERO,
+    }));
+    results.sort_by_key(|result| result.part);
+
+    results
+}
+
+/// Run `parts` of a day against `text`, an input called `input`
+///
+/// Only parsing and solving are timed, not reading `text`. Failures are
+/// returned as outcomes rather than stopping the run, and panics aren't
+/// printed as they happen.
+pub fn run_input(
+    entry: Entry,
+    input: &str,
+    text: Result<String>,
+    parts: &[Part],
+) -> Vec<SolutionResult> {
+    let start = Instant::now();
+    let solver = text
+        .map_err(|e| Outcome::Failed(format!("{:#}", e)))
+        .and_then(|text| catch(|| (entry.parser)(&text)));
+    let parse = start.elapsed();
+
+    parts
+        .iter()
+        .map(|&part| {
+            let start = Instant::now();
+            let outcome = match &solver {
+                Ok(solver) => match catch(|| solver.solve(part)) {
+                    Ok(answer) => Outcome::Solved(answer),
+                    Err(failure) => failure,
+                },
+                // Every part fails the same way when the input doesn't parse
+                Err(failure) => failure.clone(),
+            };
+
+            SolutionResult {
+                day: entry.day,
+                part: part.number(),
+                input: input.to_string(),
+                outcome,
+                parse,
+                elapsed: start.elapsed(),
+            }
+        })
+        .collect()
+}
+
+/// The registered days in `days`, or every day
+pub fn select_days(days: Option<&DayRange>) -> Vec<Entry> {
+    Solutions::days()
+        .into_iter()
+        .filter(|entry| days.is_none_or(|days| days.0.contains(&entry.day)))
+        .collect()
+}
+
+/// Run both parts of every day in `days`, or of every day
+pub fn run_days(days: Option<&DayRange>) -> Vec<SolutionResult> {
+    select_days(days)
+        .into_iter()
+        .flat_map(|entry| run_day(entry, &Part::BOTH))
+        .collect()
+}
+
+/// Run every selected part of every day
+pub fn all(args: &AllArgs) -> Result<Output> {
+    Ok(Output::Results(run_days(args.days.as_ref())))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_day_range() {
+        assert_eq!("1..7".parse(), Ok(DayRange(1..=7)));
+        assert_eq!("2..=3".parse(), Ok(DayRange(2..=3)));
+        assert_eq!("5".parse(), Ok(DayRange(5..=5)));
+        assert!("7..1".parse::<DayRange>().is_err());
+        assert!("a..b".parse::<DayRange>().is_err());
+    }
+
+    #[test]
+    fn test_catch() {
+        let panicked = catch(|| -> Result<()> { panic!("oops") });
+
+        assert_eq!(panicked, Err(Outcome::Panicked("oops".to_string())));
+        assert_eq!(
+            catch(|| -> Result<()> { Err(anyhow::anyhow!("no")) }),
+            Err(Outcome::Failed("no".to_string()))
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use clap::Clap;
+
+use crate::inputs::inputs_dir;
+
+const TEMPLATE: &str = include_str!("../templates/day.rs");
+
+#[derive(Debug, Clap)]
+pub struct NewDayArgs {
+    /// The day to create, from 1 to 25
+    day: u32,
+}
+
+/// Fill in the day template for `day`
+fn render_template(day: u32) -> String {
+    TEMPLATE.replace("{{day}}", &day.to_string())
+}
+
+/// Create a module and empty input for a new day
+///
+/// The build script finds the new module, so nothing else needs editing.
+pub fn new_day(args: &NewDayArgs) -> Result<String> {
+    if !(1..=25).contains(&args.day) {
+        return Err(anyhow!("Day {} isn't between 1 and 25", args.day));
+    }
+
+    let name = format!("day{:02}", args.day);
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let module = src.join(format!("{}.rs", name));
+    let input = inputs_dir().join(format!("{}.txt", name));
+
+    if module.exists() {
+        return Err(anyhow!("{} already exists", module.display()));
+    }
+
+    std::fs::write(&module, render_template(args.day))
+        .with_context(|| format!("Unable to write {}", module.display()))?;
+
+    let mut created = vec![module.display().to_string()];
+    if !input.exists() {
+        std::fs::write(&input, "")
+            .with_context(|| format!("Unable to write {}", input.display()))?;
+        created.push(input.display().to_string());
+    }
+
+    Ok(format!("Created {}", created.join(", ")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let module = render_template(7);
+
+        assert!(module.contains("impl Solution for Day"));
+        assert!(module.contains("fn examples()"));
+        assert!(module.contains("#[cfg(test)]"));
+        assert!(module.contains("Day 7 part 2 isn't solved yet"));
+        assert!(!module.contains("{{"));
+    }
+
+    #[test]
+    fn test_days_are_formatted() {
+        // The days are only declared in generated code, which cargo fmt can't follow
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let days = crate::Solutions::days()
+            .iter()
+            .map(|entry| src.join(format!("{}.rs", entry.name())))
+            .collect::<Vec<_>>();
+
+        let output = std::process::Command::new("rustfmt")
+            .args(["--edition", "2018", "--check"])
+            .args(&days)
+            .output()
+            .expect("Unable to run rustfmt");
+
+        assert!(
+            output.status.success(),
+            "Run rustfmt --edition 2018 src/day*.rs\n{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{anyhow, Result};
+use clap::Clap;
+
+use crate::inputs::{example, input};
+use crate::Answer;
+
+/// A day's puzzle, whose input is parsed once and shared by both parts
+///
+/// Each `src/dayNN.rs` implements this for a unit struct called `Day`.
+pub trait Solution {
+    type Parsed;
+
+    fn parse(input: &str) -> Result<Self::Parsed>;
+    fn part1(parsed: &Self::Parsed) -> Result<Answer>;
+    fn part2(parsed: &Self::Parsed) -> Result<Answer>;
+
+    /// Small inputs from the puzzle text, for `--example` and `verify --examples`
+    fn examples() -> Vec<Example> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A named example input, with the answers the puzzle text gives for it
+pub struct Example {
+    pub name: &'static str,
+    pub input: &'static str,
+    pub part1: Option<Answer>,
+    pub part2: Option<Answer>,
+}
+
+impl Example {
+    pub fn answer(&self, part: Part) -> Option<&Answer> {
+        match part {
+            Part::Part1 => self.part1.as_ref(),
+            Part::Part2 => self.part2.as_ref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Clap)]
+/// Which half of a day's puzzle to solve
+pub enum Part {
+    /// Solve the first part of the puzzle
+    Part1,
+    /// Solve the second part of the puzzle
+    Part2,
+}
+
+impl Part {
+    pub const BOTH: [Part; 2] = [Part::Part1, Part::Part2];
+
+    pub fn number(self) -> u32 {
+        match self {
+            Part::Part1 => 1,
+            Part::Part2 => 2,
+        }
+    }
+}
+
+/// A day's parsed input, ready to solve either part
+pub trait Solver {
+    fn solve(&self, part: Part) -> Result<Answer>;
+}
+
+struct Parsed<S: Solution>(S::Parsed);
+
+impl<S: Solution> Solver for Parsed<S> {
+    fn solve(&self, part: Part) -> Result<Answer> {
+        match part {
+            Part::Part1 => S::part1(&self.0),
+            Part::Part2 => S::part2(&self.0),
+        }
+    }
+}
+
+/// Parses a day's input, hiding which day it is
+pub type Parser = fn(&str) -> Result<Box<dyn Solver>>;
+
+/// The `Parser` for `S`
+pub fn parser<S>(input: &str) -> Result<Box<dyn Solver>>
+where
+    S: Solution + 'static,
+    S::Parsed: 'static,
+{
+    Ok(Box::new(Parsed::<S>(S::parse(input)?)))
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Everything the runner knows about one day
+pub struct Entry {
+    pub day: u32,
+    pub parser: Parser,
+    pub examples: fn() -> Vec<Example>,
+}
+
+impl Entry {
+    pub fn new<S>(day: u32) -> Self
+    where
+        S: Solution + 'static,
+        S::Parsed: 'static,
+    {
+        Entry {
+            day,
+            parser: parser::<S>,
+            examples: S::examples,
+        }
+    }
+
+    /// The day's module name, such as `day07`
+    pub fn name(&self) -> String {
+        format!("day{:02}", self.day)
+    }
+
+    pub fn example(&self, name: &str) -> Result<Example> {
+        let examples = (self.examples)();
+        let names = examples.iter().map(|e| e.name).collect::<Vec<_>>();
+
+        match examples.iter().find(|e| e.name == name) {
+            Some(example) => Ok(example.clone()),
+            None if names.is_empty() => Err(anyhow!("{} has no examples", self.name())),
+            None => Err(anyhow!(
+                "{} has no example called {}, try one of: {}",
+                self.name(),
+                name,
+                names.join(", ")
+            )),
+        }
+    }
+
+    /// Fail if the example chosen with `--example` has no answer for `part`
+    ///
+    /// Examples often only fit one part, and the other may not even run on them.
+    pub fn check_example(&self, part: Part) -> Result<()> {
+        let name = match example() {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        match self.example(name)?.answer(part) {
+            Some(_) => Ok(()),
+            None => Err(anyhow!(
+                "The {} example {} has no answer for part {}",
+                self.name(),
+                name,
+                part.number()
+            )),
+        }
+    }
+
+    /// Read the day's input, or the example chosen with `--example`
+    pub fn input(&self) -> Result<String> {
+        match example() {
+            Some(name) => Ok(self.example(name)?.input.to_string()),
+            None => input(&self.name()),
+        }
+    }
+}
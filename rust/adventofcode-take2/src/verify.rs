@@ -0,0 +1,272 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Clap;
+use itertools::{EitherOrBoth, Itertools};
+use serde::{Deserialize, Serialize};
+
+use crate::answer::Answer;
+use crate::runner::{run_days, run_input, select_days, DayRange, Outcome, SolutionResult};
+use crate::solution::Part;
+
+#[derive(Debug, Clap)]
+pub struct VerifyArgs {
+    /// Only check these days, either one day like `5` or an inclusive range like `1..7`
+    #[clap(long)]
+    days: Option<DayRange>,
+
+    /// The file of expected answers, `answers.json` in the crate directory by default
+    #[clap(long)]
+    answers: Option<PathBuf>,
+
+    /// Save the answers of solutions that don't have an expected answer yet
+    #[clap(long)]
+    record: bool,
+
+    /// Check the examples each day declares instead of the answers file
+    #[clap(long, conflicts_with_all = &["answers", "record"])]
+    examples: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The answer a solution is known to give for an input
+pub struct Expected {
+    pub day: u32,
+    pub part: u32,
+    /// Name of the input, `dayNN` for the usual one or whatever `--input` was given
+    pub input: String,
+    pub answer: Answer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The result of checking one solution against its expected answer
+pub enum Verdict {
+    Pass,
+    /// The solution gave a different answer, or failed to give one
+    Fail(String),
+    Missing,
+}
+
+impl Verdict {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::Pass => "pass",
+            Verdict::Fail(_) => "fail",
+            Verdict::Missing => "missing",
+        }
+    }
+
+    /// Why the solution failed
+    pub fn details(&self) -> Option<&str> {
+        match self {
+            Verdict::Fail(details) => Some(details),
+            _ => None,
+        }
+    }
+}
+
+pub fn load_answers(path: &Path) -> Result<Vec<Expected>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Unable to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Unable to parse {}", path.display()))
+}
+
+fn save_answers(path: &Path, answers: &mut Vec<Expected>) -> Result<()> {
+    answers.sort_by(|a, b| (a.day, a.part, &a.input).cmp(&(b.day, b.part, &b.input)));
+
+    let mut text = serde_json::to_string_pretty(answers)?;
+    text.push('\n');
+    std::fs::write(path, text).with_context(|| format!("Unable to write {}", path.display()))
+}
+
+/// Compare `outcome` with the expected answer, if there is one
+pub fn verdict(expected: Option<&Answer>, outcome: &Outcome) -> Verdict {
+    match (expected, outcome) {
+        (None, _) => Verdict::Missing,
+        (Some(expected), Outcome::Solved(answer)) if expected == answer => Verdict::Pass,
+        (Some(expected), Outcome::Solved(answer)) => {
+            Verdict::Fail(diff(&expected.to_string(), &answer.to_string()))
+        }
+        (Some(_), failure) => Verdict::Fail(format!("{}: {}", failure.status(), failure.text())),
+    }
+}
+
+/// A line by line diff, with `-` for expected lines and `+` for actual ones
+fn diff(expected: &str, actual: &str) -> String {
+    expected
+        .lines()
+        .zip_longest(actual.lines())
+        .flat_map(|pair| match pair {
+            EitherOrBoth::Both(e, a) if e == a => vec![format!("  {}", e)],
+            EitherOrBoth::Both(e, a) => vec![format!("- {}", e), format!("+ {}", a)],
+            EitherOrBoth::Left(e) => vec![format!("- {}", e)],
+            EitherOrBoth::Right(a) => vec![format!("+ {}", a)],
+        })
+        .join("\n")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Every solution that was checked, for main to print in the chosen format
+pub struct Report {
+    pub checks: Vec<(SolutionResult, Verdict)>,
+    /// A note saying how many new answers were saved and where, if any were
+    pub recorded: Option<String>,
+}
+
+impl Report {
+    fn count(&self, label: &str) -> usize {
+        self.checks
+            .iter()
+            .filter(|(_, v)| v.label() == label)
+            .count()
+    }
+
+    pub fn failed(&self) -> bool {
+        self.count("fail") > 0
+    }
+
+    /// The count of each verdict, after the note about recorded answers
+    pub fn summary(&self) -> String {
+        let counts = format!(
+            "{} passed, {} failed, {} missing",
+            self.count("pass"),
+            self.count("fail"),
+            self.count("missing")
+        );
+
+        match &self.recorded {
+            Some(recorded) => format!("{}\n{}", recorded, counts),
+            None => counts,
+        }
+    }
+}
+
+/// Check every selected solution
+pub fn verify(args: &VerifyArgs) -> Result<Report> {
+    if args.examples {
+        return Ok(verify_examples(args.days.as_ref()));
+    }
+
+    let path = args
+        .answers
+        .clone()
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("answers.json"));
+    let mut answers = load_answers(&path)?;
+    let mut recorded = Vec::new();
+    let mut checks = Vec::new();
+
+    for result in run_days(args.days.as_ref()) {
+        let expected = answers
+            .iter()
+            .find(|e| e.day == result.day && e.part == result.part && e.input == result.input)
+            .map(|e| &e.answer);
+        let verdict = verdict(expected, &result.outcome);
+
+        if let (true, Verdict::Missing, Outcome::Solved(answer)) =
+            (args.record, &verdict, &result.outcome)
+        {
+            recorded.push(Expected {
+                day: result.day,
+                part: result.part,
+                input: result.input.clone(),
+                answer: answer.clone(),
+            });
+        }
+
+        checks.push((result, verdict));
+    }
+
+    let recorded = match recorded.len() {
+        0 => None,
+        count => {
+            answers.extend(recorded);
+            save_answers(&path, &mut answers)?;
+            Some(format!("Recorded {} answers in {}", count, path.display()))
+        }
+    };
+
+    Ok(Report { checks, recorded })
+}
+
+/// Check every part of every example that has an expected answer
+fn verify_examples(days: Option<&DayRange>) -> Report {
+    let mut checks = Vec::new();
+
+    for entry in select_days(days) {
+        for example in (entry.examples)() {
+            let parts = Part::BOTH
+                .iter()
+                .copied()
+                .filter(|&part| example.answer(part).is_some())
+                .collect::<Vec<_>>();
+            let input = format!("example:{}", example.name);
+            let text = Ok(example.input.to_string());
+
+            for (result, &part) in run_input(entry, &input, text, &parts)
+                .into_iter()
+                .zip(&parts)
+            {
+                let verdict = verdict(example.answer(part), &result.outcome);
+                checks.push((result, verdict));
+            }
+        }
+    }
+
+    Report {
+        checks,
+        recorded: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_verdict() {
+        let solved = Outcome::Solved(Answer::Number(42));
+
+        assert_eq!(verdict(Some(&Answer::Number(42)), &solved), Verdict::Pass);
+        assert_eq!(verdict(None, &solved), Verdict::Missing);
+        // The same text isn't the same answer
+        assert_eq!(
+            verdict(Some(&Answer::Text("42".to_string())), &solved),
+            Verdict::Fail("  42".to_string())
+        );
+        assert_eq!(
+            verdict(Some(&Answer::Number(41)), &solved),
+            Verdict::Fail("- 41\n+ 42".to_string())
+        );
+        assert_eq!(
+            verdict(
+                Some(&Answer::Number(42)),
+                &Outcome::Panicked("oops".to_string())
+            ),
+            Verdict::Fail("panic: oops".to_string())
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff("#.\n.#\n##", "#.\n##"), "  #.\n- .#\n+ ##\n- ##");
+    }
+
+    #[test]
+    fn test_examples() {
+        let report = verify_examples(None);
+
+        assert!(report.count("pass") > 0);
+        assert!(!report.failed());
+    }
+
+    #[test]
+    fn test_answers_file_is_valid() {
+        let answers = load_answers(Path::new("answers.json")).unwrap();
+
+        assert!(answers.iter().any(|e| e.day == 9 && e.part == 1));
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::{anyhow, Result};
+
+use crate::solution::Example;
+use crate::{Answer, Solution};
+
+pub struct Day;
+
+impl Solution for Day {
+    type Parsed = Vec<String>;
+
+    fn parse(input: &str) -> Result<Self::Parsed> {
+        Ok(input.lines().map(str::to_string).collect())
+    }
+
+    fn part1(lines: &Self::Parsed) -> Result<Answer> {
+        Err(anyhow!(
+            "Day {{day}} part 1 isn't solved yet, {} lines of input",
+            lines.len()
+        ))
+    }
+
+    fn part2(lines: &Self::Parsed) -> Result<Answer> {
+        Err(anyhow!(
+            "Day {{day}} part 2 isn't solved yet, {} lines of input",
+            lines.len()
+        ))
+    }
+
+    fn examples() -> Vec<Example> {
+        // Paste the examples from the puzzle text here, and fill in their
+        // answers so that `verify --examples` checks them
+        vec![Example {
+            name: "example",
+            input: "",
+            part1: None,
+            part2: None,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore = "fill in the example and its part 1 answer"]
+    fn test_part_one_example() {
+        let example = Day::examples().remove(0);
+        let parsed = Day::parse(example.input).unwrap();
+
+        assert_eq!(Some(Day::part1(&parsed).unwrap()), example.part1);
+    }
+
+    #[test]
+    #[ignore = "fill in the example and its part 2 answer"]
+    fn test_part_two_example() {
+        let example = Day::examples().remove(0);
+        let parsed = Day::parse(example.input).unwrap();
+
+        assert_eq!(Some(Day::part2(&parsed).unwrap()), example.part2);
+    }
+}